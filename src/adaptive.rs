@@ -0,0 +1,104 @@
+use crate::{image::Image, vec3::Color3};
+
+/// Luminance below which the noise threshold is taken as absolute instead of relative,
+/// so dark pixels don't sample forever.
+const MIN_LUMINANCE: f64 = 0.01;
+
+/// Settings to stop sampling a pixel once its estimate is precise enough.
+///
+/// The camera's `samples_per_pixel` is the upper budget for each pixel.
+#[derive(Clone, Copy, Debug)]
+pub struct AdaptiveSampling {
+    /// Count of samples each pixel gets before checking for convergence
+    pub min_samples: u32,
+    /// Relative standard error of the pixel luminance at which a pixel is converged
+    pub threshold: f64,
+}
+
+impl AdaptiveSampling {
+    pub fn new(min_samples: u32, threshold: f64) -> Self {
+        Self {
+            min_samples: min_samples.max(2),
+            threshold,
+        }
+    }
+
+    pub fn converged(&self, stats: &PixelStats) -> bool {
+        stats.count >= self.min_samples
+            && stats.standard_error() <= self.threshold * stats.mean_luminance.max(MIN_LUMINANCE)
+    }
+}
+
+/// Running mean of the samples of one pixel and variance of their luminance.
+#[derive(Clone, Debug, Default)]
+pub struct PixelStats {
+    pub count: u32,
+    sum: Color3,
+    mean_luminance: f64,
+    /// Sum of squared differences from the mean luminance (Welford's algorithm)
+    m2: f64,
+}
+
+impl PixelStats {
+    pub fn add(&mut self, color: Color3) {
+        self.count += 1;
+        self.sum += color;
+
+        let luminance = color.luminance();
+        let delta = luminance - self.mean_luminance;
+        self.mean_luminance += delta / f64::from(self.count);
+        self.m2 += delta * (luminance - self.mean_luminance);
+    }
+
+    pub fn mean(&self) -> Color3 {
+        match self.count {
+            0 => Color3::default(),
+            count => self.sum / f64::from(count),
+        }
+    }
+
+    /// Returns the standard error of the mean luminance.
+    pub fn standard_error(&self) -> f64 {
+        match self.count {
+            0 | 1 => f64::INFINITY,
+            count => {
+                let count = f64::from(count);
+                (self.m2 / (count - 1.) / count).sqrt()
+            }
+        }
+    }
+}
+
+/// Returns an image coloring each pixel from blue (few samples) to red (`max_samples`).
+pub fn heatmap(width: u32, height: u32, sample_counts: &[u32], max_samples: u32) -> Image {
+    let mut image = Image::new(width, height);
+    for (pixel, &count) in image.pixels_mut().iter_mut().zip(sample_counts) {
+        let t = f64::from(count) / f64::from(max_samples.max(1));
+        *pixel = Color3::new(t, 0., 1. - t);
+    }
+    image
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn converged() {
+        // Arrange
+        let adaptive = AdaptiveSampling::new(4, 0.01);
+        let mut constant = PixelStats::default();
+        let mut noisy = PixelStats::default();
+
+        // Act
+        for k in 0..4 {
+            constant.add(Color3::new(0.5, 0.5, 0.5));
+            noisy.add(Color3::new(1., 1., 1.) * f64::from(k % 2));
+        }
+
+        // Assert
+        assert!(adaptive.converged(&constant));
+        assert!(!adaptive.converged(&noisy));
+        assert_eq!(noisy.mean(), Color3::new(0.5, 0.5, 0.5));
+    }
+}
@@ -0,0 +1,281 @@
+//! Arbitrary output variables: data about the first surface seen through each pixel, written
+//! next to the beauty image for denoising and compositing.
+
+use std::{
+    collections::HashMap,
+    fs::File,
+    io::{self, BufWriter, Write},
+    path::PathBuf,
+    rc::Rc,
+};
+
+use crate::{
+    exr::write_exr,
+    hittable::HitRecord,
+    image::Image,
+    ray::Ray,
+    vec3::{Color3, Point3, Vec3},
+};
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Aov {
+    /// Surface color of the material, or the background color on a miss
+    Albedo,
+    /// Distance from the ray origin to the hit, infinite on a miss
+    Depth,
+    /// Materials numbered from one in the order they are first seen, zero on a miss
+    MaterialId,
+    /// World-space normal facing the ray
+    Normal,
+    /// Objects numbered from one by their index in the scene, zero on a miss
+    ObjectId,
+    /// World-space hit position
+    Position,
+}
+
+impl Aov {
+    pub const ALL: [Aov; 6] = [
+        Aov::Albedo,
+        Aov::Depth,
+        Aov::MaterialId,
+        Aov::Normal,
+        Aov::ObjectId,
+        Aov::Position,
+    ];
+
+    pub fn name(self) -> &'static str {
+        match self {
+            Aov::Albedo => "albedo",
+            Aov::Depth => "depth",
+            Aov::MaterialId => "material_id",
+            Aov::Normal => "normal",
+            Aov::ObjectId => "object_id",
+            Aov::Position => "position",
+        }
+    }
+
+    /// Channel names in a multi-channel file, following the usual compositing conventions.
+    fn channels(self) -> &'static [&'static str] {
+        match self {
+            Aov::Albedo => &["albedo.R", "albedo.G", "albedo.B"],
+            Aov::Depth => &["Z"],
+            Aov::MaterialId => &["materialId"],
+            Aov::Normal => &["N.X", "N.Y", "N.Z"],
+            Aov::ObjectId => &["objectId"],
+            Aov::Position => &["P.X", "P.Y", "P.Z"],
+        }
+    }
+}
+
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum AovFormat {
+    /// One PFM file per AOV, plus one for the linear beauty image
+    #[default]
+    Pfm,
+    /// One OpenEXR file with the beauty image and every AOV as channels
+    Exr,
+}
+
+/// Which AOVs to write and where.
+#[derive(Clone, Debug)]
+pub struct AovOutput {
+    pub aovs: Vec<Aov>,
+    pub format: AovFormat,
+    /// File path; for PFM output its extension is replaced by `<name>.pfm` for each AOV
+    pub path: PathBuf,
+}
+
+impl AovOutput {
+    /// Write every AOV to `path`.
+    pub fn new(path: impl Into<PathBuf>, format: AovFormat) -> Self {
+        Self {
+            aovs: Aov::ALL.to_vec(),
+            format,
+            path: path.into(),
+        }
+    }
+
+    pub fn with_aovs(mut self, aovs: &[Aov]) -> Self {
+        self.aovs = aovs.to_vec();
+        self
+    }
+
+    /// Write the beauty image and the requested AOVs, which must have the same size.
+    pub fn write(&self, beauty: &Image, layers: &[(Aov, Image)]) -> io::Result<()> {
+        match self.format {
+            AovFormat::Pfm => {
+                beauty.save_pfm(self.path.with_extension("beauty.pfm"))?;
+                for (aov, image) in layers {
+                    image.save_pfm(self.path.with_extension(format!("{}.pfm", aov.name())))?;
+                }
+                Ok(())
+            }
+            AovFormat::Exr => {
+                let channel = |image: &Image, c: usize| {
+                    image
+                        .pixels()
+                        .iter()
+                        .map(|p| [p.x, p.y, p.z][c] as f32)
+                        .collect::<Vec<_>>()
+                };
+                let mut channels = ["R", "G", "B"]
+                    .into_iter()
+                    .enumerate()
+                    .map(|(c, name)| (name, channel(beauty, c)))
+                    .collect::<Vec<_>>();
+                for (aov, image) in layers {
+                    for (c, name) in aov.channels().iter().enumerate() {
+                        channels.push((name, channel(image, c)));
+                    }
+                }
+
+                let mut out = BufWriter::new(File::create(self.path.with_extension("exr"))?);
+                write_exr(&mut out, beauty.width(), beauty.height(), &channels)?;
+                out.flush()
+            }
+        }
+    }
+}
+
+/// Per-pixel AOV accumulators.
+///
+/// Continuous AOVs are averaged over the samples of each pixel, or for geometric ones over
+/// the samples that hit a surface, with normals scaled back to unit length. The IDs come
+/// from the first sample, since averaging them is meaningless.
+#[derive(Debug)]
+pub struct AovBuffers {
+    albedo: Vec<Color3>,
+    depth: Vec<f64>,
+    /// Count of samples that hit a surface
+    hits: Vec<u32>,
+    material_id: Vec<u32>,
+    /// IDs of the materials seen so far, by address
+    materials: HashMap<*const (), u32>,
+    normal: Vec<Vec3>,
+    object_id: Vec<u32>,
+    position: Vec<Point3>,
+    samples: Vec<u32>,
+    width: u32,
+    height: u32,
+}
+
+impl AovBuffers {
+    pub fn new(width: u32, height: u32) -> Self {
+        let n = (width * height) as usize;
+        Self {
+            albedo: vec![Color3::default(); n],
+            depth: vec![0.; n],
+            hits: vec![0; n],
+            material_id: vec![0; n],
+            materials: HashMap::new(),
+            normal: vec![Vec3::default(); n],
+            object_id: vec![0; n],
+            position: vec![Point3::default(); n],
+            samples: vec![0; n],
+            width,
+            height,
+        }
+    }
+
+    /// Record the first hit of the camera ray `r` through pixel i,j, or the `background`
+    /// color it sees if it hits nothing.
+    pub fn add_sample(
+        &mut self,
+        i: u32,
+        j: u32,
+        r: &Ray,
+        rec: Option<&HitRecord>,
+        background: Color3,
+    ) {
+        let idx = (j * self.width + i) as usize;
+        let first = self.samples[idx] == 0;
+        self.samples[idx] += 1;
+
+        let Some(rec) = rec else {
+            self.albedo[idx] += background;
+            return;
+        };
+        self.albedo[idx] += rec.material.albedo(rec);
+        self.depth[idx] += rec.t * r.direction.length();
+        self.hits[idx] += 1;
+        self.normal[idx] += rec.normal;
+        self.position[idx] += rec.p;
+
+        if first {
+            let next_id = self.materials.len() as u32 + 1;
+            let address = Rc::as_ptr(&rec.material) as *const ();
+            self.material_id[idx] = *self.materials.entry(address).or_insert(next_id);
+            self.object_id[idx] = rec.object_id;
+        }
+    }
+
+    /// Returns the AOV as an image; scalar AOVs are stored in every channel.
+    pub fn image(&self, aov: Aov) -> Image {
+        let mut image = Image::new(self.width, self.height);
+        let per_sample = |sum: Vec3, count: u32| match count {
+            0 => Vec3::default(),
+            _ => sum / f64::from(count),
+        };
+        let gray = |v: f64| Color3::new(v, v, v);
+
+        for (idx, pixel) in image.pixels_mut().iter_mut().enumerate() {
+            let (samples, hits) = (self.samples[idx], self.hits[idx]);
+            *pixel = match aov {
+                Aov::Albedo => per_sample(self.albedo[idx], samples),
+                Aov::Depth => match hits {
+                    0 => gray(f64::INFINITY),
+                    _ => gray(self.depth[idx] / f64::from(hits)),
+                },
+                Aov::MaterialId => gray(self.material_id[idx].into()),
+                Aov::Normal => {
+                    let normal = per_sample(self.normal[idx], hits);
+                    match normal.near_zero() {
+                        true => normal,
+                        false => normal.unit_vector(),
+                    }
+                }
+                Aov::ObjectId => gray(self.object_id[idx].into()),
+                Aov::Position => per_sample(self.position[idx], hits),
+            };
+        }
+        image
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::material::Lambertian;
+
+    #[test]
+    fn add_sample() {
+        // Arrange
+        let mut aovs = AovBuffers::new(2, 1);
+        let r = Ray::new(Point3::new(0., 0., 0.), Vec3::new(0., 0., -2.));
+        let material = Rc::new(Lambertian::new(Color3::new(0.5, 0.2, 0.1)));
+        let mut rec = HitRecord::new(material, Point3::new(0., 0., -3.), 1.5);
+        rec.set_face_normal(&r, &Vec3::new(0., 0., 1.));
+        rec.object_id = 4;
+        let background = Color3::new(0.1, 0.1, 1.);
+
+        // Act
+        aovs.add_sample(0, 0, &r, Some(&rec), background);
+        aovs.add_sample(0, 0, &r, None, background);
+        aovs.add_sample(1, 0, &r, None, background);
+
+        // Assert
+        let depth = aovs.image(Aov::Depth);
+        assert_eq!(depth.get(0, 0).x, 3.);
+        assert_eq!(depth.get(1, 0).x, f64::INFINITY);
+        assert!((aovs.image(Aov::Albedo).get(0, 0) - Color3::new(0.3, 0.15, 0.55)).near_zero());
+        assert_eq!(aovs.image(Aov::Normal).get(0, 0), Vec3::new(0., 0., 1.));
+        assert_eq!(aovs.image(Aov::Normal).get(1, 0), Vec3::new(0., 0., 0.));
+        assert_eq!(
+            aovs.image(Aov::Position).get(0, 0),
+            Point3::new(0., 0., -3.)
+        );
+        assert_eq!(aovs.image(Aov::ObjectId).get(0, 0).x, 4.);
+        assert_eq!(aovs.image(Aov::MaterialId).get(0, 0).x, 1.);
+        assert_eq!(aovs.image(Aov::MaterialId).get(1, 0).x, 0.);
+    }
+}
@@ -0,0 +1,168 @@
+use std::{f64::consts::PI, io, path::Path, rc::Rc};
+
+use crate::{image::Image, sampling::concentric_disk};
+
+/// Shape of the lens opening, which is the shape of out-of-focus highlights.
+#[derive(Clone, Debug, Default)]
+pub enum Aperture {
+    #[default]
+    Circle,
+    /// Regular polygon of `blades` sides, rotated by `rotation` degrees
+    Polygon { blades: u32, rotation: f64 },
+    /// Opening with the transmission of a grayscale image
+    Image(Rc<ApertureImage>),
+}
+
+impl Aperture {
+    /// Maps `u` in [0,1)² to a point in the aperture, within [-1,1]².
+    pub fn sample(&self, u: (f64, f64)) -> (f64, f64) {
+        match self {
+            Self::Circle => {
+                let p = concentric_disk(u);
+                (p.x, p.y)
+            }
+            Self::Polygon { blades, rotation } => {
+                // Pick a triangle between the center and one side, and reuse the remainder
+                // of `u.0` to sample it uniformly.
+                let blades = f64::from((*blades).max(3));
+                let k = (u.0 * blades).floor();
+                let u0 = u.0 * blades - k;
+                let corner = |k: f64| {
+                    let angle = rotation.to_radians() + 2. * PI * k / blades;
+                    (angle.cos(), angle.sin())
+                };
+                let (a, b) = (corner(k), corner(k + 1.));
+                let s = u0.sqrt();
+                (
+                    s * ((1. - u.1) * a.0 + u.1 * b.0),
+                    s * ((1. - u.1) * a.1 + u.1 * b.1),
+                )
+            }
+            Self::Image(image) => image.sample(u),
+        }
+    }
+}
+
+/// Aperture transmission from a grayscale image, sampled in proportion to the pixel values.
+#[derive(Debug)]
+pub struct ApertureImage {
+    width: usize,
+    height: usize,
+    /// Cumulative distribution over the columns of each row, normalized per row
+    conditional_cdf: Vec<f64>,
+    /// Cumulative distribution over the rows, normalized
+    marginal_cdf: Vec<f64>,
+}
+
+impl ApertureImage {
+    /// Create an aperture from `width` × `height` transmission weights in row-major order.
+    ///
+    /// Returns `None` if no weight is positive.
+    pub fn new(width: usize, height: usize, weights: &[f64]) -> Option<Self> {
+        let mut conditional_cdf = Vec::with_capacity(width * height);
+        let mut marginal_cdf = Vec::with_capacity(height);
+        let mut total = 0.;
+
+        for row in weights.chunks_exact(width).take(height) {
+            let mut sum = 0.;
+            for &w in row {
+                sum += w.max(0.);
+                conditional_cdf.push(sum);
+            }
+            let cdf = &mut conditional_cdf[marginal_cdf.len() * width..];
+            cdf.iter_mut()
+                .for_each(|c| *c /= sum.max(f64::MIN_POSITIVE));
+            total += sum;
+            marginal_cdf.push(total);
+        }
+        if marginal_cdf.len() < height || total <= 0. {
+            return None;
+        }
+        marginal_cdf.iter_mut().for_each(|c| *c /= total);
+
+        Some(Self {
+            width,
+            height,
+            conditional_cdf,
+            marginal_cdf,
+        })
+    }
+
+    /// Load an aperture from a PGM or PPM file, using the luminance as transmission.
+    pub fn load(path: impl AsRef<Path>) -> io::Result<Self> {
+        let image = Image::load_pnm(path)?;
+        let weights = image
+            .pixels()
+            .iter()
+            .map(|c| c.luminance())
+            .collect::<Vec<_>>();
+        Self::new(image.width() as _, image.height() as _, &weights)
+            .ok_or_else(|| io::Error::new(io::ErrorKind::InvalidData, "aperture image is black"))
+    }
+
+    /// Maps `u` in [0,1)² to a point in [-1,1]², with the image top at `y = 1`.
+    fn sample(&self, u: (f64, f64)) -> (f64, f64) {
+        let (row, fy) = sample_cdf(&self.marginal_cdf, u.1);
+        let cdf = &self.conditional_cdf[row * self.width..(row + 1) * self.width];
+        let (column, fx) = sample_cdf(cdf, u.0);
+
+        let x = (column as f64 + fx) / self.width as f64;
+        let y = (row as f64 + fy) / self.height as f64;
+        (2. * x - 1., 1. - 2. * y)
+    }
+}
+
+/// Returns the index of the bucket of `cdf` containing `u`, and the position of `u`
+/// within the bucket.
+fn sample_cdf(cdf: &[f64], u: f64) -> (usize, f64) {
+    let index = cdf.partition_point(|&c| c <= u).min(cdf.len() - 1);
+    let lower = match index {
+        0 => 0.,
+        _ => cdf[index - 1],
+    };
+    let width = cdf[index] - lower;
+    let frac = match width > 0. {
+        true => ((u - lower) / width).clamp(0., 1.),
+        false => 0.5,
+    };
+    (index, frac)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn sample_polygon() {
+        // Arrange
+        let aperture = Aperture::Polygon {
+            blades: 4,
+            rotation: 45.,
+        };
+
+        // Act
+        let points = [(0., 0.), (0.999, 0.999), (0.3, 0.7), (0.6, 0.1)].map(|u| aperture.sample(u));
+
+        // Assert
+        for (x, y) in points {
+            // Square with corners at distance one from the center.
+            let half = 0.5_f64.sqrt() + 1e-12;
+            assert!(x.abs() <= half && y.abs() <= half);
+        }
+    }
+
+    #[test]
+    fn sample_image() {
+        // Arrange
+        let weights = [0., 0., 0., 1.];
+        let aperture = ApertureImage::new(2, 2, &weights).unwrap();
+
+        // Act
+        let points = [(0., 0.), (0.5, 0.5), (0.999, 0.999)].map(|u| aperture.sample(u));
+
+        // Assert
+        for (x, y) in points {
+            assert!((0. ..=1.).contains(&x) && (-1. ..=0.).contains(&y));
+        }
+    }
+}
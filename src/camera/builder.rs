@@ -0,0 +1,522 @@
+use std::{error::Error, fmt, ops::Range, path::PathBuf};
+
+use crate::{
+    adaptive::AdaptiveSampling,
+    aov::AovOutput,
+    camera::{Aperture, Camera, CropOutput, CropWindow, PhysicalCamera, Projection},
+    color::DisplayTransform,
+    denoise::Denoiser,
+    filter::{BoxFilter, Filter},
+    integrator::{Integrator, PathIntegrator},
+    post::PostEffect,
+    sampler::{Sampler, StratifiedSampler},
+    vec3::{Point3, Vec3},
+};
+
+/// Builder for a [`Camera`], with every setting named and defaulted.
+#[derive(Debug)]
+pub struct CameraBuilder {
+    adaptive: Option<AdaptiveSampling>,
+    aovs: Option<AovOutput>,
+    aperture: Aperture,
+    cat_eye: f64,
+    chromatic_aberration: f64,
+    crop: Option<CropWindow>,
+    defocus_angle: f64,
+    denoiser: Option<Denoiser>,
+    display: DisplayTransform,
+    exposure: f64,
+    filter: Box<dyn Filter>,
+    focus_dist: f64,
+    heatmap: Option<PathBuf>,
+    image_height: u32,
+    image_width: u32,
+    integrator: Option<Box<dyn Integrator>>,
+    lookat: Point3,
+    lookfrom: Point3,
+    max_depth: u32,
+    physical: Option<PhysicalCamera>,
+    pixel_aspect: f64,
+    post_effects: Vec<Box<dyn PostEffect>>,
+    projection: Projection,
+    sampler: Option<Box<dyn Sampler>>,
+    samples_per_pixel: Option<u32>,
+    vfov: f64,
+    vup: Vec3,
+}
+
+impl Default for CameraBuilder {
+    fn default() -> Self {
+        Self {
+            adaptive: None,
+            aovs: None,
+            aperture: Aperture::default(),
+            cat_eye: 0.,
+            chromatic_aberration: 0.,
+            crop: None,
+            defocus_angle: 0.,
+            denoiser: None,
+            display: DisplayTransform::default(),
+            exposure: 1.,
+            filter: Box::new(BoxFilter::default()),
+            focus_dist: 10.,
+            heatmap: None,
+            image_height: 100,
+            image_width: 100,
+            integrator: None,
+            lookat: Point3::new(0., 0., -1.),
+            lookfrom: Point3::new(0., 0., 0.),
+            max_depth: 10,
+            physical: None,
+            pixel_aspect: 1.,
+            post_effects: Vec::new(),
+            projection: Projection::default(),
+            sampler: None,
+            samples_per_pixel: None,
+            vfov: 90.,
+            vup: Vec3::new(0., 1., 0.),
+        }
+    }
+}
+
+impl CameraBuilder {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Stop sampling each pixel once it has converged, using at most `samples_per_pixel`.
+    pub fn adaptive_sampling(mut self, adaptive: AdaptiveSampling) -> Self {
+        self.adaptive = Some(adaptive);
+        self
+    }
+
+    /// Write AOVs of the first hit through each pixel after rendering.
+    pub fn aovs(mut self, aovs: AovOutput) -> Self {
+        self.aovs = Some(aovs);
+        self
+    }
+
+    /// Shape of the lens opening, scaled to the defocus disk
+    pub fn aperture(mut self, aperture: Aperture) -> Self {
+        self.aperture = aperture;
+        self
+    }
+
+    /// Strength of the clipping of the aperture towards the image corners, from 0 (none) to
+    /// 2 (closed at the corners)
+    pub fn cat_eye(mut self, cat_eye: f64) -> Self {
+        self.cat_eye = cat_eye;
+        self
+    }
+
+    /// Relative change of the image scale between the color channels, e.g. 0.005
+    pub fn chromatic_aberration(mut self, chromatic_aberration: f64) -> Self {
+        self.chromatic_aberration = chromatic_aberration;
+        self
+    }
+
+    /// Render only the pixels within `x` × `y`, and write them as selected by `output`
+    pub fn crop_window(mut self, x: Range<u32>, y: Range<u32>, output: CropOutput) -> Self {
+        self.crop = Some(CropWindow { x, y, output });
+        self
+    }
+
+    /// Variation angle of rays through each pixel, in degrees
+    pub fn defocus_angle(mut self, defocus_angle: f64) -> Self {
+        self.defocus_angle = defocus_angle;
+        self
+    }
+
+    /// Denoise the image after rendering, guided by the first-hit albedo and normals.
+    pub fn denoiser(mut self, denoiser: Denoiser) -> Self {
+        self.denoiser = Some(denoiser);
+        self
+    }
+
+    /// Tone mapping and color space conversion from the rendered radiance to the output
+    pub fn display_transform(mut self, display: DisplayTransform) -> Self {
+        self.display = display;
+        self
+    }
+
+    /// Factor applied to the radiance of each sample, before the display transform's
+    /// exposure compensation
+    pub fn exposure(mut self, exposure: f64) -> Self {
+        self.exposure = exposure;
+        self
+    }
+
+    /// Filter weighting samples into the pixels around them
+    pub fn filter(mut self, filter: impl Filter + 'static) -> Self {
+        self.filter = Box::new(filter);
+        self
+    }
+
+    /// Distance from camera lookfrom point to plane of perfect focus
+    pub fn focus_dist(mut self, focus_dist: f64) -> Self {
+        self.focus_dist = focus_dist;
+        self
+    }
+
+    /// Write a heatmap of the samples taken for each pixel to `path` after rendering.
+    pub fn heatmap(mut self, path: impl Into<PathBuf>) -> Self {
+        self.heatmap = Some(path.into());
+        self
+    }
+
+    /// Light transport algorithm, replacing the default path tracer.
+    ///
+    /// The integrator's own settings take the place of `max_depth`.
+    pub fn integrator(mut self, integrator: impl Integrator + 'static) -> Self {
+        self.integrator = Some(Box::new(integrator));
+        self
+    }
+
+    /// Point camera is looking at
+    pub fn lookat(mut self, lookat: Point3) -> Self {
+        self.lookat = lookat;
+        self
+    }
+
+    /// Point camera is looking from
+    pub fn lookfrom(mut self, lookfrom: Point3) -> Self {
+        self.lookfrom = lookfrom;
+        self
+    }
+
+    /// Maximum number of ray bounces into scene, for the default path tracer
+    pub fn max_depth(mut self, max_depth: u32) -> Self {
+        self.max_depth = max_depth;
+        self
+    }
+
+    /// Settings of a real camera, replacing `vfov`, `defocus_angle` and `exposure`
+    pub fn physical(mut self, physical: PhysicalCamera) -> Self {
+        self.physical = Some(physical);
+        self
+    }
+
+    /// Ratio of width over height of a single pixel, e.g. `4. / 3.` for a 1.33x anamorphic
+    /// squeeze
+    pub fn pixel_aspect(mut self, pixel_aspect: f64) -> Self {
+        self.pixel_aspect = pixel_aspect;
+        self
+    }
+
+    /// Add an effect to the end of the post-processing stack.
+    pub fn post_effect(mut self, effect: impl PostEffect + 'static) -> Self {
+        self.post_effects.push(Box::new(effect));
+        self
+    }
+
+    /// Mapping from raster positions to camera rays
+    pub fn projection(mut self, projection: Projection) -> Self {
+        self.projection = projection;
+        self
+    }
+
+    /// Rendered image width and height in pixel count
+    pub fn resolution(mut self, image_width: u32, image_height: u32) -> Self {
+        self.image_width = image_width;
+        self.image_height = image_height;
+        self
+    }
+
+    /// Sampler generating pixel and lens samples, replacing the default stratified one.
+    ///
+    /// The count of samples for each pixel is taken from the sampler, so it can't be
+    /// combined with `samples_per_pixel`.
+    pub fn sampler(mut self, sampler: impl Sampler + 'static) -> Self {
+        self.sampler = Some(Box::new(sampler));
+        self
+    }
+
+    /// Count of stratified random samples for each pixel, 10 by default
+    pub fn samples_per_pixel(mut self, samples_per_pixel: u32) -> Self {
+        self.samples_per_pixel = Some(samples_per_pixel);
+        self
+    }
+
+    /// Vertical view angle (field of view), in degrees
+    pub fn vfov(mut self, vfov: f64) -> Self {
+        self.vfov = vfov;
+        self
+    }
+
+    /// Camera-relative "up" direction
+    pub fn vup(mut self, vup: Vec3) -> Self {
+        self.vup = vup;
+        self
+    }
+
+    pub fn build(mut self) -> Result<Camera, CameraError> {
+        if self.image_width == 0 || self.image_height == 0 {
+            return Err(CameraError::InvalidResolution(
+                self.image_width,
+                self.image_height,
+            ));
+        }
+        if let Some(crop) = &self.crop {
+            let inside = |r: &Range<u32>, size| r.start < r.end && r.end <= size;
+            if !(inside(&crop.x, self.image_width) && inside(&crop.y, self.image_height)) {
+                return Err(CameraError::InvalidCropWindow(crop.clone()));
+            }
+        }
+        if !(self.pixel_aspect.is_finite() && self.pixel_aspect > 0.) {
+            return Err(CameraError::InvalidPixelAspect(self.pixel_aspect));
+        }
+        if !(self.focus_dist.is_finite() && self.focus_dist > 0.) {
+            return Err(CameraError::InvalidFocusDist(self.focus_dist));
+        }
+        if let Some(physical) = self.physical {
+            if !physical.is_valid() {
+                return Err(CameraError::InvalidPhysicalCamera(physical));
+            }
+            let aspect =
+                f64::from(self.image_width) * self.pixel_aspect / f64::from(self.image_height);
+            self.vfov = physical.vfov(aspect);
+            self.defocus_angle = physical.defocus_angle(self.focus_dist);
+            self.exposure = physical.exposure();
+        }
+        if !(self.vfov > 0. && self.vfov < 180.) {
+            return Err(CameraError::InvalidVfov(self.vfov));
+        }
+        if !(self.defocus_angle >= 0. && self.defocus_angle < 180.) {
+            return Err(CameraError::InvalidDefocusAngle(self.defocus_angle));
+        }
+        if !(0. ..=2.).contains(&self.cat_eye) {
+            return Err(CameraError::InvalidCatEye(self.cat_eye));
+        }
+        if !(self.chromatic_aberration.is_finite() && self.chromatic_aberration.abs() < 1.) {
+            return Err(CameraError::InvalidChromaticAberration(
+                self.chromatic_aberration,
+            ));
+        }
+        if !(self.exposure.is_finite() && self.exposure >= 0.) {
+            return Err(CameraError::InvalidExposure(self.exposure));
+        }
+        let filter_radius = self.filter.radius();
+        if !(filter_radius.is_finite() && filter_radius > 0.) {
+            return Err(CameraError::InvalidFilterRadius(filter_radius));
+        }
+
+        match self.projection {
+            Projection::Orthographic { height } if !(height.is_finite() && height > 0.) => {
+                return Err(CameraError::InvalidProjection(self.projection));
+            }
+            Projection::Fisheye { fov: angle } | Projection::Cylindrical { hfov: angle }
+                if !(angle > 0. && angle <= 360.) =>
+            {
+                return Err(CameraError::InvalidProjection(self.projection));
+            }
+            _ => {}
+        }
+
+        let view = self.lookfrom - self.lookat;
+        if view.near_zero() {
+            return Err(CameraError::LookfromIsLookat);
+        }
+        if self.vup.cross(&view).length() <= 1e-8 * self.vup.length() * view.length() {
+            return Err(CameraError::VupParallelToView);
+        }
+
+        let sampler = match (self.sampler, self.samples_per_pixel) {
+            (Some(_), Some(_)) => return Err(CameraError::SamplerWithSamplesPerPixel),
+            (Some(sampler), None) => sampler,
+            (None, samples_per_pixel) => {
+                Box::new(StratifiedSampler::new(samples_per_pixel.unwrap_or(10)))
+            }
+        };
+        if sampler.samples_per_pixel() == 0 {
+            return Err(CameraError::ZeroSamplesPerPixel);
+        }
+
+        let integrator = match self.integrator {
+            Some(integrator) => integrator,
+            None => Box::new(PathIntegrator::new(self.max_depth)),
+        };
+
+        let image_width = f64::from(self.image_width);
+        let image_height = f64::from(self.image_height);
+
+        let center = self.lookfrom;
+
+        // Determine viewport dimensions.
+        let theta = self.vfov.to_radians();
+        let h = (theta / 2.).tan();
+        let viewport_height = match self.projection {
+            Projection::Orthographic { height } => height,
+            _ => 2. * h * self.focus_dist,
+        };
+        let viewport_width = viewport_height * (image_width * self.pixel_aspect / image_height);
+
+        // Calculate the u,v,w unit basis vectors for the camera coordinate frame.
+        let w = view.unit_vector();
+        let u = self.vup.cross(&w).unit_vector();
+        let v = w.cross(&u);
+
+        // Calculate the vectors across the horizontal and down the vertical viewport edges.
+        let viewport_u = viewport_width * u; // Vector across viewport horizontal edge
+        let viewport_v = viewport_height * -v; // Vector down viewport vertical edge
+
+        // Calculate the horizontal and vertical delta vectors from pixel to pixel.
+        let pixel_delta_u = viewport_u / image_width;
+        let pixel_delta_v = viewport_v / image_height;
+
+        // Calculate the location of the upper left pixel.
+        let viewport_upper_left = center - self.focus_dist * w - viewport_u / 2. - viewport_v / 2.;
+        let pixel00_loc = viewport_upper_left + 0.5 * (pixel_delta_u + pixel_delta_v);
+
+        // Calculate the camera defocus disk basis vector.
+        let defocus_radius = self.focus_dist * (self.defocus_angle / 2.).to_radians().tan();
+        let defocus_disk_u = u * defocus_radius;
+        let defocus_disk_v = v * defocus_radius;
+
+        Ok(Camera {
+            adaptive: self.adaptive,
+            aovs: self.aovs,
+            aperture: self.aperture,
+            cat_eye: self.cat_eye,
+            center,
+            chromatic_aberration: self.chromatic_aberration,
+            crop: self.crop,
+            defocus_angle: self.defocus_angle,
+            defocus_disk_u,
+            defocus_disk_v,
+            denoiser: self.denoiser,
+            display: self.display,
+            exposure: self.exposure,
+            filter: self.filter,
+            focus_dist: self.focus_dist,
+            heatmap: self.heatmap,
+            image_height: self.image_height,
+            image_width: self.image_width,
+            integrator,
+            pixel00_loc,
+            pixel_delta_u,
+            pixel_delta_v,
+            pixel_aspect: self.pixel_aspect,
+            post_effects: self.post_effects,
+            projection: self.projection,
+            sampler,
+            u,
+            v,
+            w,
+            vfov: self.vfov,
+        })
+    }
+}
+
+/// Invalid camera settings passed to a [`CameraBuilder`].
+#[derive(Clone, Debug, PartialEq)]
+pub enum CameraError {
+    InvalidCatEye(f64),
+    InvalidChromaticAberration(f64),
+    InvalidCropWindow(CropWindow),
+    InvalidDefocusAngle(f64),
+    InvalidExposure(f64),
+    InvalidFilterRadius(f64),
+    InvalidFocusDist(f64),
+    InvalidPhysicalCamera(PhysicalCamera),
+    InvalidPixelAspect(f64),
+    InvalidProjection(Projection),
+    InvalidResolution(u32, u32),
+    InvalidVfov(f64),
+    LookfromIsLookat,
+    SamplerWithSamplesPerPixel,
+    VupParallelToView,
+    ZeroSamplesPerPixel,
+}
+
+impl fmt::Display for CameraError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::InvalidCatEye(c) => write!(f, "cat-eye must be in [0,2], got {c}"),
+            Self::InvalidChromaticAberration(c) => {
+                write!(f, "chromatic aberration must be in (-1,1), got {c}")
+            }
+            Self::InvalidCropWindow(c) => {
+                write!(
+                    f,
+                    "crop window must be a non-empty part of the image: {c:?}"
+                )
+            }
+            Self::InvalidDefocusAngle(a) => {
+                write!(f, "defocus angle must be in [0,180) degrees, got {a}")
+            }
+            Self::InvalidExposure(e) => write!(f, "exposure must not be negative, got {e}"),
+            Self::InvalidFilterRadius(r) => write!(f, "filter radius must be positive, got {r}"),
+            Self::InvalidFocusDist(d) => write!(f, "focus distance must be positive, got {d}"),
+            Self::InvalidPhysicalCamera(p) => {
+                write!(f, "physical camera settings must be positive: {p:?}")
+            }
+            Self::InvalidPixelAspect(a) => write!(f, "pixel aspect must be positive, got {a}"),
+            Self::InvalidProjection(p) => write!(f, "invalid projection parameters: {p:?}"),
+            Self::InvalidResolution(w, h) => {
+                write!(f, "resolution must be positive in both axes, got {w}x{h}")
+            }
+            Self::InvalidVfov(a) => write!(f, "vertical fov must be in (0,180) degrees, got {a}"),
+            Self::LookfromIsLookat => write!(f, "lookfrom and lookat must be different points"),
+            Self::SamplerWithSamplesPerPixel => {
+                write!(
+                    f,
+                    "samples per pixel must be set on the sampler, not the camera"
+                )
+            }
+            Self::VupParallelToView => write!(f, "vup must not be parallel to the view direction"),
+            Self::ZeroSamplesPerPixel => write!(f, "samples per pixel must be positive"),
+        }
+    }
+}
+
+impl Error for CameraError {}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::filter::TentFilter;
+
+    #[test]
+    fn build() {
+        // Arrange
+        let builder = CameraBuilder::new()
+            .resolution(1920, 1080)
+            .pixel_aspect(4. / 3.);
+
+        // Act
+        let cam = builder.build().unwrap();
+
+        // Assert
+        assert_eq!((cam.image_width, cam.image_height), (1920, 1080));
+        let pixel_aspect = cam.pixel_delta_u.length() / cam.pixel_delta_v.length();
+        assert!((pixel_aspect - 4. / 3.).abs() < 1e-12);
+    }
+
+    #[test]
+    fn build_invalid() {
+        // Arrange
+        let p = Point3::new(1., 2., 3.);
+        let builders = [
+            CameraBuilder::new().resolution(1920, 0),
+            CameraBuilder::new().lookfrom(p).lookat(p),
+            CameraBuilder::new().vup(Vec3::new(0., 0., 2.)),
+            CameraBuilder::new()
+                .sampler(StratifiedSampler::new(4))
+                .samples_per_pixel(8),
+            CameraBuilder::new().filter(TentFilter::new(0.)),
+        ];
+
+        // Act
+        let errors = builders.map(|b| b.build().unwrap_err());
+
+        // Assert
+        let result = [
+            CameraError::InvalidResolution(1920, 0),
+            CameraError::LookfromIsLookat,
+            CameraError::VupParallelToView,
+            CameraError::SamplerWithSamplesPerPixel,
+            CameraError::InvalidFilterRadius(0.),
+        ];
+        assert_eq!(errors, result);
+    }
+}
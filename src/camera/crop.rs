@@ -0,0 +1,81 @@
+use std::ops::Range;
+
+/// Rectangle of pixels to render, leaving the framing of the full image unchanged.
+#[derive(Clone, Debug, PartialEq)]
+pub struct CropWindow {
+    pub x: Range<u32>,
+    pub y: Range<u32>,
+    pub output: CropOutput,
+}
+
+/// Image written for a [`CropWindow`].
+#[derive(Clone, Copy, Debug, Default, PartialEq)]
+pub enum CropOutput {
+    /// Only the pixels of the crop window
+    #[default]
+    Crop,
+    /// The crop window at its place in a full-size image, black elsewhere
+    FullFrame,
+}
+
+#[cfg(test)]
+mod tests {
+    use std::rc::Rc;
+
+    use super::*;
+    use crate::{
+        camera::{Camera, CameraBuilder},
+        hittable::Sphere,
+        integrator::DebugIntegrator,
+        material::Lambertian,
+        sampler::SobolSampler,
+        scene::Scene,
+        vec3::{Color3, Point3},
+    };
+
+    fn camera() -> CameraBuilder {
+        // The normals of a sphere vary across the frame, and with a Sobol sampler every
+        // pixel sees the same rays however many others are rendered.
+        Camera::builder()
+            .integrator(DebugIntegrator::default())
+            .resolution(12, 8)
+            .sampler(SobolSampler::new(4, 0))
+    }
+
+    #[test]
+    fn crop_window() {
+        // Arrange
+        let mut scene = Scene::new();
+        let material = Rc::new(Lambertian::new(Color3::new(0.5, 0.5, 0.5)));
+        scene.add(Sphere::new(Point3::new(0.3, 0.2, -2.), 1., material));
+        let full = camera().build().unwrap();
+        let (x, y) = (3..9, 2..5);
+        let cropped = camera()
+            .crop_window(x.clone(), y.clone(), CropOutput::Crop)
+            .build()
+            .unwrap();
+        let full_frame = camera()
+            .crop_window(x.clone(), y.clone(), CropOutput::FullFrame)
+            .build()
+            .unwrap();
+
+        // Act
+        let full = full.render_image(&scene).unwrap();
+        let cropped = cropped.render_image(&scene).unwrap();
+        let full_frame = full_frame.render_image(&scene).unwrap();
+
+        // Assert
+        assert_eq!((cropped.width(), cropped.height()), (6, 3));
+        assert_eq!(cropped.pixels(), full.crop(x.clone(), y.clone()).pixels());
+        assert_eq!((full_frame.width(), full_frame.height()), (12, 8));
+        for j in 0..8 {
+            for i in 0..12 {
+                let expected = match x.contains(&i) && y.contains(&j) {
+                    true => full.get(i, j),
+                    false => Color3::new(0., 0., 0.),
+                };
+                assert_eq!(full_frame.get(i, j), expected);
+            }
+        }
+    }
+}
@@ -0,0 +1,414 @@
+mod aperture;
+mod builder;
+mod crop;
+mod physical;
+mod projection;
+
+use std::{
+    io::{self, BufWriter},
+    ops::Range,
+    path::PathBuf,
+    time::Instant,
+};
+
+use crate::{
+    adaptive::{heatmap, AdaptiveSampling, PixelStats},
+    aov::{Aov, AovBuffers, AovOutput},
+    color::DisplayTransform,
+    denoise::Denoiser,
+    film::Film,
+    filter::Filter,
+    hittable::Hittable,
+    image::Image,
+    integrator::Integrator,
+    interval::Interval,
+    post::{PostEffect, Stage},
+    progressive::Progressive,
+    ray::{Ray, RayKind},
+    sampler::{PixelSample, Sampler},
+    scene::Scene,
+    vec3::{Color3, Point3, Vec3},
+};
+
+pub use self::{
+    aperture::{Aperture, ApertureImage},
+    builder::{CameraBuilder, CameraError},
+    crop::{CropOutput, CropWindow},
+    physical::PhysicalCamera,
+    projection::Projection,
+};
+
+#[derive(Debug)]
+pub struct Camera {
+    adaptive: Option<AdaptiveSampling>,
+    aovs: Option<AovOutput>,
+    aperture: Aperture,
+    /// Strength of the clipping of the aperture off-axis
+    cat_eye: f64,
+    center: Point3,
+    /// Relative change of the image scale between the color channels
+    chromatic_aberration: f64,
+    crop: Option<CropWindow>,
+    defocus_angle: f64,
+    defocus_disk_u: Vec3,
+    defocus_disk_v: Vec3,
+    denoiser: Option<Denoiser>,
+    display: DisplayTransform,
+    /// Factor applied to the radiance of each sample
+    exposure: f64,
+    filter: Box<dyn Filter>,
+    focus_dist: f64,
+    heatmap: Option<PathBuf>,
+    image_height: u32,
+    image_width: u32,
+    integrator: Box<dyn Integrator>,
+    pixel00_loc: Point3,
+    pixel_delta_u: Vec3,
+    pixel_delta_v: Vec3,
+    pixel_aspect: f64,
+    /// Effects applied in order, each at its stage of the output
+    post_effects: Vec<Box<dyn PostEffect>>,
+    projection: Projection,
+    sampler: Box<dyn Sampler>,
+    /// Camera frame basis vectors
+    u: Vec3,
+    v: Vec3,
+    w: Vec3,
+    vfov: f64,
+}
+
+impl Camera {
+    pub fn builder() -> CameraBuilder {
+        CameraBuilder::new()
+    }
+
+    pub fn render(&self, scene: &Scene) -> io::Result<()> {
+        self.render_image(scene)?
+            .write_ppm(&mut BufWriter::new(io::stdout().lock()))
+    }
+
+    /// Render the image and return it after post effects and the display transform, as
+    /// `render` would write it.
+    pub fn render_image(&self, scene: &Scene) -> io::Result<Image> {
+        let samples_per_pixel = self.sampler.samples_per_pixel();
+        let (xs, ys) = self.pixel_bounds();
+        let mut state = self.render_state();
+        self.integrator.preprocess(scene);
+
+        for j in ys.clone() {
+            eprint!("\rScanlines remaining: {:05}", ys.end - j);
+            for i in xs.clone() {
+                self.sample_pixel(i, j, scene, &mut state, samples_per_pixel);
+            }
+        }
+        eprintln!("\rDone.{}", " ".repeat(25));
+
+        self.finish(&state)
+    }
+
+    /// Render the image in passes over all pixels, until the target sample count or the
+    /// time budget is reached.
+    ///
+    /// The time budget is checked between passes, so every pixel gets the same count of
+    /// samples, short of adaptive sampling, and the last pass may overrun the budget.
+    pub fn render_progressive(&self, scene: &Scene, progressive: &Progressive) -> io::Result<()> {
+        self.render_progressive_image(scene, progressive)?
+            .write_ppm(&mut BufWriter::new(io::stdout().lock()))
+    }
+
+    /// Render the image progressively and return it, as `render_progressive` would write it.
+    pub fn render_progressive_image(
+        &self,
+        scene: &Scene,
+        progressive: &Progressive,
+    ) -> io::Result<Image> {
+        let start = Instant::now();
+        let mut last_snapshot = start;
+        let (xs, ys) = self.pixel_bounds();
+        let mut state = self.render_state();
+        self.integrator.preprocess(scene);
+
+        let target_samples = match (progressive.target_samples, progressive.time_budget) {
+            (Some(target_samples), _) => target_samples,
+            (None, Some(_)) => u32::MAX,
+            (None, None) => self.sampler.samples_per_pixel(),
+        };
+        let out_of_time = || {
+            progressive
+                .time_budget
+                .is_some_and(|b| start.elapsed() >= b)
+        };
+
+        let mut pass_samples_total = 0;
+        while pass_samples_total < target_samples {
+            let pass_samples = progressive
+                .pass_samples
+                .min(target_samples - pass_samples_total);
+            let mut sampled_any = false;
+
+            for j in ys.clone() {
+                for i in xs.clone() {
+                    let idx = (j * self.image_width + i) as usize;
+                    let count = state.pixels[idx].count;
+                    self.sample_pixel(i, j, scene, &mut state, pass_samples);
+                    sampled_any |= state.pixels[idx].count > count;
+                }
+            }
+            pass_samples_total += pass_samples;
+            eprint!(
+                "\rSamples per pixel: {:05}, elapsed: {:.1}s",
+                pass_samples_total,
+                start.elapsed().as_secs_f64()
+            );
+
+            // Every pixel has converged.
+            if !sampled_any {
+                break;
+            }
+            if let Some((path, interval)) = &progressive.snapshots {
+                if last_snapshot.elapsed() >= *interval {
+                    self.display_image(state.film.image()).save_ppm(path)?;
+                    last_snapshot = Instant::now();
+                }
+            }
+            if out_of_time() {
+                break;
+            }
+        }
+        eprintln!("\rDone.{}", " ".repeat(40));
+
+        self.finish(&state)
+    }
+
+    fn render_state(&self) -> RenderState {
+        let (width, height) = (self.image_width, self.image_height);
+        RenderState {
+            aovs: (self.aovs.is_some() || self.denoiser.is_some())
+                .then(|| AovBuffers::new(width, height)),
+            film: Film::new(width, height),
+            pixels: vec![PixelStats::default(); (width * height) as _],
+        }
+    }
+
+    /// Add up to `samples` samples in the pixel at location i,j to the film, stopping early
+    /// once the pixel has converged.
+    fn sample_pixel(&self, i: u32, j: u32, scene: &Scene, state: &mut RenderState, samples: u32) {
+        let stats = &mut state.pixels[(j * self.image_width + i) as usize];
+        let first = stats.count;
+        // The first hit is only needed for AOVs and holdouts.
+        let first_hit = state.aovs.is_some() || scene.world.has_holdouts();
+        for s in first..first.saturating_add(samples) {
+            if self.adaptive.is_some_and(|a| a.converged(stats)) {
+                break;
+            }
+
+            let mut sample = PixelSample::new(self.sampler.as_ref(), (i, j), s);
+            let (u, v) = sample.get_2d();
+            let x = f64::from(i) + u;
+            let y = f64::from(j) + v;
+            let (ray_x, ray_y, mask) = self.chromatic_aberration(x, y, &mut sample);
+            let radiance = match self.get_ray(ray_x, ray_y, &mut sample) {
+                Some(r) => {
+                    let rec = first_hit
+                        .then(|| scene.world.hit(&r, &Interval::new(0.001, f64::INFINITY)))
+                        .flatten();
+                    if let Some(aovs) = &mut state.aovs {
+                        aovs.add_sample(i, j, &r, rec.as_ref(), scene.background(&r));
+                    }
+                    match rec.is_some_and(|rec| scene.is_holdout(&rec)) {
+                        true => Color3::new(0., 0., 0.),
+                        false => self.integrator.li(&r, scene, &mut sample),
+                    }
+                }
+                None => Color3::new(0., 0., 0.),
+            };
+            let color = self.exposure * mask * radiance;
+
+            stats.add(color);
+            state.film.add_sample(self.filter.as_ref(), x, y, color);
+        }
+    }
+
+    /// Report sample statistics, write the heatmap and AOVs if requested and return the
+    /// image to display, denoised if requested.
+    fn finish(&self, state: &RenderState) -> io::Result<Image> {
+        let sample_counts = state.pixels.iter().map(|p| p.count).collect::<Vec<_>>();
+        if self.adaptive.is_some() {
+            let (xs, ys) = self.pixel_bounds();
+            let total = sample_counts.iter().map(|&c| u64::from(c)).sum::<u64>();
+            eprintln!(
+                "Average samples per pixel: {:.1}",
+                total as f64 / (xs.len() * ys.len()) as f64
+            );
+        }
+        if let Some(path) = &self.heatmap {
+            let (width, height) = (self.image_width, self.image_height);
+            let max_samples = sample_counts.iter().copied().max().unwrap_or(1);
+            let heatmap = heatmap(width, height, &sample_counts, max_samples);
+            self.output_image(heatmap).save_ppm(path)?;
+        }
+
+        let mut image = state.film.image();
+        if let (Some(output), Some(aovs)) = (&self.aovs, &state.aovs) {
+            let layers = output
+                .aovs
+                .iter()
+                .map(|&aov| (aov, self.output_image(aovs.image(aov))))
+                .collect::<Vec<_>>();
+            output.write(&self.output_image(image.clone()), &layers)?;
+        }
+        if let (Some(denoiser), Some(aovs)) = (&self.denoiser, &state.aovs) {
+            // Only denoise the rendered pixels, so the black surround doesn't bleed in.
+            let (xs, ys) = self.pixel_bounds();
+            let crop = |image: Image| image.crop(xs.clone(), ys.clone());
+            let denoised = denoiser.denoise(
+                &crop(image.clone()),
+                &crop(aovs.image(Aov::Albedo)),
+                &crop(aovs.image(Aov::Normal)),
+            );
+            image.paste(xs.start, ys.start, &denoised);
+        }
+
+        Ok(self.display_image(image))
+    }
+
+    /// Returns the image to write for the rendered radiance, after post effects and the
+    /// display transform.
+    fn display_image(&self, image: Image) -> Image {
+        let mut image = self.output_image(image);
+        self.apply_post_effects(Stage::Hdr, &mut image);
+        let mut image = self.display.apply_image(&image);
+        self.apply_post_effects(Stage::Display, &mut image);
+        image
+    }
+
+    fn apply_post_effects(&self, stage: Stage, image: &mut Image) {
+        for effect in self.post_effects.iter().filter(|e| e.stage() == stage) {
+            effect.apply(image);
+        }
+    }
+
+    /// Returns the pixels to render along each axis.
+    fn pixel_bounds(&self) -> (Range<u32>, Range<u32>) {
+        match &self.crop {
+            Some(crop) => (crop.x.clone(), crop.y.clone()),
+            None => (0..self.image_width, 0..self.image_height),
+        }
+    }
+
+    /// Returns the part of a full-size image to write.
+    fn output_image(&self, image: Image) -> Image {
+        let Some(crop) = &self.crop else {
+            return image;
+        };
+
+        // Samples near the edge of the crop window also splat onto pixels outside of it.
+        let cropped = image.crop(crop.x.clone(), crop.y.clone());
+        match crop.output {
+            CropOutput::Crop => cropped,
+            CropOutput::FullFrame => {
+                let mut full = Image::new(self.image_width, self.image_height);
+                full.paste(crop.x.start, crop.y.start, &cropped);
+                full
+            }
+        }
+    }
+
+    /// Get a sampled camera ray through raster position x,y, originating from the camera
+    /// defocus disk.
+    ///
+    /// Pixel i,j covers `[i, i+1) × [j, j+1)` in raster space. Returns `None` if the
+    /// projection doesn't cover the position.
+    fn get_ray(&self, x: f64, y: f64, sample: &mut PixelSample) -> Option<Ray> {
+        let center = match self.projection {
+            Projection::Perspective => self.center,
+            Projection::Orthographic { .. } => self.viewport_point(x, y) + self.focus_dist * self.w,
+            _ => {
+                let sx = x / f64::from(self.image_width);
+                let sy = y / f64::from(self.image_height);
+                let aspect =
+                    f64::from(self.image_width) * self.pixel_aspect / f64::from(self.image_height);
+                let d = self
+                    .projection
+                    .panoramic_direction(sx, sy, aspect, self.vfov)?;
+                let ray_direction = d.x * self.u + d.y * self.v - d.z * self.w;
+                return Some(Ray::new(self.center, ray_direction).with_kind(RayKind::Camera));
+            }
+        };
+
+        let pixel_sample = self.viewport_point(x, y);
+        let ray_origin = match self.defocus_angle <= 0. {
+            true => center,
+            false => self.defocus_disk_sample(center, x, y, sample.get_2d())?,
+        };
+        let ray_direction = pixel_sample - ray_origin;
+
+        Some(Ray::new(ray_origin, ray_direction).with_kind(RayKind::Camera))
+    }
+
+    /// Returns the point on the viewport, in the plane of perfect focus, at raster position
+    /// x,y.
+    fn viewport_point(&self, x: f64, y: f64) -> Point3 {
+        // `pixel00_loc` is the center of the upper left pixel.
+        self.pixel00_loc + ((x - 0.5) * self.pixel_delta_u) + ((y - 0.5) * self.pixel_delta_v)
+    }
+
+    /// Maps `u` in [0,1)² to a point in the aperture around `center`, for the ray through
+    /// raster position x,y.
+    ///
+    /// Returns `None` if the point is blocked by the cat-eye clipping.
+    fn defocus_disk_sample(&self, center: Point3, x: f64, y: f64, u: (f64, f64)) -> Option<Point3> {
+        let p = self.aperture.sample(u);
+
+        // Off-axis, the lens barrel clips the aperture with a disk moving towards the image
+        // center. `offset` is one at the image corners.
+        if self.cat_eye > 0. {
+            let (cx, cy) = self.image_center();
+            let offset = ((x - cx) / cx, (cy - y) / cy);
+            let clip_center = (
+                self.cat_eye * offset.0 / 2_f64.sqrt(),
+                self.cat_eye * offset.1 / 2_f64.sqrt(),
+            );
+            if (p.0 - clip_center.0).powi(2) + (p.1 - clip_center.1).powi(2) > 1. {
+                return None;
+            }
+        }
+
+        Some(center + p.0 * self.defocus_disk_u + p.1 * self.defocus_disk_v)
+    }
+
+    /// Picks one color channel for the sample at raster position x,y, and scales the
+    /// position about the image center for that channel.
+    ///
+    /// Returns the scaled position and the weight of the sample in each channel.
+    fn chromatic_aberration(&self, x: f64, y: f64, sample: &mut PixelSample) -> (f64, f64, Color3) {
+        if self.chromatic_aberration == 0. {
+            return (x, y, Color3::new(1., 1., 1.));
+        }
+
+        // Red is scaled down and blue up, around green.
+        let channel = (sample.get_1d() * 3.).floor().min(2.);
+        let scale = 1. + self.chromatic_aberration * (channel - 1.);
+        let (cx, cy) = self.image_center();
+        let mask = match channel as u32 {
+            0 => Color3::new(3., 0., 0.),
+            1 => Color3::new(0., 3., 0.),
+            _ => Color3::new(0., 0., 3.),
+        };
+        (cx + (x - cx) * scale, cy + (y - cy) * scale, mask)
+    }
+
+    fn image_center(&self) -> (f64, f64) {
+        (
+            f64::from(self.image_width) / 2.,
+            f64::from(self.image_height) / 2.,
+        )
+    }
+}
+
+/// Accumulated results of a render.
+struct RenderState {
+    aovs: Option<AovBuffers>,
+    film: Film,
+    pixels: Vec<PixelStats>,
+}
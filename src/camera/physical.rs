@@ -0,0 +1,119 @@
+/// Camera described by the settings of a real camera body and lens.
+///
+/// The lens is a thin lens focused at the camera's focus distance; focus breathing is not
+/// modelled. Scene radiance is taken to be in cd/m² for the exposure, and the shutter time
+/// only affects the exposure.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct PhysicalCamera {
+    /// Sensor width in mm
+    pub sensor_width: f64,
+    /// Sensor height in mm
+    pub sensor_height: f64,
+    /// Lens focal length in mm
+    pub focal_length: f64,
+    /// Ratio of focal length over aperture diameter
+    pub f_number: f64,
+    /// Shutter time in seconds
+    pub shutter: f64,
+    /// Sensor sensitivity
+    pub iso: f64,
+    /// Count of world units in one meter, to scale the aperture to the scene
+    pub units_per_meter: f64,
+}
+
+impl PhysicalCamera {
+    /// A 36×24 mm full frame camera at 1/125 s and ISO 100.
+    pub fn full_frame(focal_length: f64, f_number: f64) -> Self {
+        Self {
+            sensor_width: 36.,
+            sensor_height: 24.,
+            focal_length,
+            f_number,
+            shutter: 1. / 125.,
+            iso: 100.,
+            units_per_meter: 1.,
+        }
+    }
+
+    pub fn is_valid(&self) -> bool {
+        [
+            self.sensor_width,
+            self.sensor_height,
+            self.focal_length,
+            self.f_number,
+            self.shutter,
+            self.iso,
+            self.units_per_meter,
+        ]
+        .into_iter()
+        .all(|x| x.is_finite() && x > 0.)
+    }
+
+    /// Returns the vertical view angle in degrees, for an image with the ratio `aspect` of
+    /// width over height fitted inside the sensor.
+    pub fn vfov(&self, aspect: f64) -> f64 {
+        let height = match aspect >= self.sensor_width / self.sensor_height {
+            true => self.sensor_width / aspect,
+            false => self.sensor_height,
+        };
+        2. * (height / (2. * self.focal_length)).atan().to_degrees()
+    }
+
+    /// Returns the aperture radius in world units.
+    pub fn aperture_radius(&self) -> f64 {
+        let diameter_mm = self.focal_length / self.f_number;
+        diameter_mm / 2. / 1000. * self.units_per_meter
+    }
+
+    /// Returns the angle in degrees the aperture spans, seen from the plane of perfect focus
+    /// at `focus_dist`.
+    pub fn defocus_angle(&self, focus_dist: f64) -> f64 {
+        2. * (self.aperture_radius() / focus_dist).atan().to_degrees()
+    }
+
+    /// Returns the exposure value at ISO 100.
+    pub fn ev100(&self) -> f64 {
+        (self.f_number * self.f_number / self.shutter * 100. / self.iso).log2()
+    }
+
+    /// Returns the factor mapping scene luminance to the sensor, so that the luminance
+    /// saturating the sensor maps to one.
+    pub fn exposure(&self) -> f64 {
+        // Saturation based sensitivity: `78 / (100 * q) * 2^EV100`, with the lens and
+        // vignetting attenuation `q = 0.65`.
+        let max_luminance = 1.2 * 2_f64.powf(self.ev100());
+        1. / max_luminance
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn vfov() {
+        // Arrange
+        let cam = PhysicalCamera::full_frame(50., 2.8);
+
+        // Act
+        let vfov = cam.vfov(1.5);
+
+        // Assert
+        assert!((vfov - 26.9915).abs() < 1e-4);
+    }
+
+    #[test]
+    fn ev100() {
+        // Arrange
+        let cam = PhysicalCamera {
+            shutter: 1.,
+            ..PhysicalCamera::full_frame(50., 1.)
+        };
+
+        // Act
+        let ev100 = cam.ev100();
+
+        // Assert
+        assert_eq!(ev100, 0.);
+    }
+}
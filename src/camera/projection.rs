@@ -0,0 +1,94 @@
+use std::f64::consts::PI;
+
+use crate::vec3::Vec3;
+
+/// How raster positions map to camera rays.
+///
+/// Perspective and orthographic cameras look through the viewport at the focus distance
+/// and support defocus blur. The panoramic projections cast all rays from the camera center
+/// and ignore the defocus angle.
+#[derive(Clone, Copy, Debug, Default, PartialEq)]
+pub enum Projection {
+    /// Pinhole or thin lens with the vertical view angle `vfov`
+    #[default]
+    Perspective,
+    /// Parallel rays along the view direction, through a viewport `height` world units tall
+    Orthographic { height: f64 },
+    /// Full 360°×180° panorama, with longitude across and latitude down the image
+    Equirectangular,
+    /// Equidistant fisheye, with `fov` degrees across the image circle fitted to the image
+    Fisheye { fov: f64 },
+    /// `hfov` degrees of longitude across the image, with perspective along the vertical
+    /// view angle `vfov`
+    Cylindrical { hfov: f64 },
+}
+
+impl Projection {
+    /// Returns the direction of the ray through normalized image position `(sx, sy)` in
+    /// [0,1)², in the camera frame with x right, y up and z forward.
+    ///
+    /// - `aspect`: Ratio of image width over height, including the pixel aspect
+    /// - `vfov`: Vertical view angle, in degrees
+    ///
+    /// Returns `None` outside of the image circle of a fisheye, and for the projections
+    /// which are not panoramic.
+    pub fn panoramic_direction(&self, sx: f64, sy: f64, aspect: f64, vfov: f64) -> Option<Vec3> {
+        match *self {
+            Self::Perspective | Self::Orthographic { .. } => None,
+            Self::Equirectangular => {
+                // Longitude `phi` and latitude `theta`.
+                let phi = (sx - 0.5) * 2. * PI;
+                let theta = (0.5 - sy) * PI;
+                let cos_theta = theta.cos();
+                Some(Vec3::new(
+                    cos_theta * phi.sin(),
+                    theta.sin(),
+                    cos_theta * phi.cos(),
+                ))
+            }
+            Self::Fisheye { fov } => {
+                // Image circle inscribed in the shorter image side.
+                let px = (sx - 0.5) * aspect;
+                let py = 0.5 - sy;
+                let radius = aspect.min(1.) / 2.;
+                let r = (px * px + py * py).sqrt() / radius;
+                (r <= 1.).then(|| {
+                    let theta = r * fov.to_radians() / 2.;
+                    let phi = py.atan2(px);
+                    let sin_theta = theta.sin();
+                    Vec3::new(sin_theta * phi.cos(), sin_theta * phi.sin(), theta.cos())
+                })
+            }
+            Self::Cylindrical { hfov } => {
+                let phi = (sx - 0.5) * hfov.to_radians();
+                let h = (vfov.to_radians() / 2.).tan() * (1. - 2. * sy);
+                Some(Vec3::new(phi.sin(), h, phi.cos()))
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn panoramic_direction() {
+        // Arrange
+        let projections = [
+            Projection::Equirectangular,
+            Projection::Fisheye { fov: 180. },
+            Projection::Cylindrical { hfov: 360. },
+        ];
+
+        // Act
+        let centers = projections.map(|p| p.panoramic_direction(0.5, 0.5, 2., 90.));
+        let corner = Projection::Fisheye { fov: 180. }.panoramic_direction(0., 0., 2., 90.);
+
+        // Assert
+        for center in centers {
+            assert!((center.unwrap() - Vec3::new(0., 0., 1.)).near_zero());
+        }
+        assert!(corner.is_none());
+    }
+}
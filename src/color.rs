@@ -0,0 +1,253 @@
+//! Color spaces and the display transform from scene-referred radiance to display values.
+
+use crate::{image::Image, vec3::Color3};
+
+/// Row-major 3×3 matrix acting on colors.
+type Matrix = [[f64; 3]; 3];
+
+/// Linear ACEScg (AP1 primaries, D60) to linear Rec. 709 (D65), with Bradford adaptation.
+const ACESCG_TO_REC709: Matrix = [
+    [1.70505, -0.62179, -0.08326],
+    [-0.13026, 1.14080, -0.01055],
+    [-0.02400, -0.12897, 1.15297],
+];
+
+/// Rec. 709 into the space of the fitted ACES RRT and ODT, by Stephen Hill.
+const ACES_INPUT: Matrix = [
+    [0.59719, 0.35458, 0.04823],
+    [0.07600, 0.90834, 0.01566],
+    [0.02840, 0.13383, 0.83777],
+];
+const ACES_OUTPUT: Matrix = [
+    [1.60475, -0.53108, -0.07367],
+    [-0.10208, 1.10813, -0.00605],
+    [-0.00327, -0.07276, 1.07602],
+];
+
+/// Rec. 709 into the AgX base space, and back, from Benjamin Wrensch's minimal AgX.
+const AGX_INSET: Matrix = [
+    [0.842479062253094, 0.0784335999999992, 0.0792237451477643],
+    [0.0423282422610123, 0.878468636469772, 0.0791661274605434],
+    [0.0423756549057051, 0.0784336, 0.879142973793104],
+];
+const AGX_OUTSET: Matrix = [
+    [1.19687900512017, -0.0980208811401368, -0.0990297440797205],
+    [-0.0528968517574562, 1.15190312990417, -0.0989611768448433],
+    [-0.0529716355144438, -0.0980434501171241, 1.15107367264116],
+];
+/// Range of exposures around middle gray that AgX maps onto the display, in stops.
+const AGX_MIN_EV: f64 = -12.47393;
+const AGX_MAX_EV: f64 = 4.026069;
+
+/// Color space of the radiance the renderer works in, i.e. of material and sky colors.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum WorkingSpace {
+    /// Linear Rec. 709 primaries, as used by sRGB
+    #[default]
+    Rec709,
+    /// Linear ACES AP1 primaries, with a wider gamut than Rec. 709
+    AcesCg,
+}
+
+/// Curve compressing scene radiance into the displayable range.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum ToneMap {
+    /// Clip every channel at one
+    #[default]
+    Clamp,
+    /// `L / (1 + L)` on the luminance, keeping the hue
+    Reinhard,
+    /// Fit of the ACES reference rendering and sRGB output transforms
+    AcesFilmic,
+    /// Polynomial fit of the AgX base look, which desaturates towards white like film
+    Agx,
+}
+
+impl ToneMap {
+    /// Maps a linear Rec. 709 color to a linear display color in [0,1].
+    pub fn apply(self, color: Color3) -> Color3 {
+        let color = Color3::new(color.x.max(0.), color.y.max(0.), color.z.max(0.));
+        let mapped = match self {
+            ToneMap::Clamp => color,
+            ToneMap::Reinhard => {
+                let luminance = color.luminance();
+                match luminance > 0. {
+                    true => color / (1. + luminance),
+                    false => color,
+                }
+            }
+            ToneMap::AcesFilmic => {
+                let v = transform(&ACES_INPUT, color);
+                let fit = |v: f64| {
+                    let a = v * (v + 0.0245786) - 0.000090537;
+                    let b = v * (0.983729 * v + 0.4329510) + 0.238081;
+                    a / b
+                };
+                transform(&ACES_OUTPUT, Color3::new(fit(v.x), fit(v.y), fit(v.z)))
+            }
+            ToneMap::Agx => {
+                let v = transform(&AGX_INSET, color);
+                let contrast = |v: f64| {
+                    let x = (v.max(1e-10).log2().clamp(AGX_MIN_EV, AGX_MAX_EV) - AGX_MIN_EV)
+                        / (AGX_MAX_EV - AGX_MIN_EV);
+                    let x2 = x * x;
+                    let x4 = x2 * x2;
+                    15.5 * x4 * x2 - 40.14 * x4 * x + 31.96 * x4 - 6.868 * x2 * x
+                        + 0.4298 * x2
+                        + 0.1191 * x
+                        - 0.00232
+                };
+                // The curve is fitted to display values with a 2.2 gamma.
+                let v = transform(
+                    &AGX_OUTSET,
+                    Color3::new(contrast(v.x), contrast(v.y), contrast(v.z)),
+                );
+                Color3::new(
+                    v.x.max(0.).powf(2.2),
+                    v.y.max(0.).powf(2.2),
+                    v.z.max(0.).powf(2.2),
+                )
+            }
+        };
+        Color3::new(
+            mapped.x.clamp(0., 1.),
+            mapped.y.clamp(0., 1.),
+            mapped.z.clamp(0., 1.),
+        )
+    }
+}
+
+/// Transform from rendered radiance to linear display colors, to be encoded with
+/// [`srgb_oetf`] when written.
+#[derive(Clone, Copy, Debug, Default, PartialEq)]
+pub struct DisplayTransform {
+    /// Exposure adjustment in stops, applied before tone mapping. It scales the radiance
+    /// on top of the camera's linear exposure, by `2^exposure_compensation`.
+    pub exposure_compensation: f64,
+    pub tone_map: ToneMap,
+    pub working_space: WorkingSpace,
+}
+
+impl DisplayTransform {
+    pub fn new(tone_map: ToneMap) -> Self {
+        Self {
+            tone_map,
+            ..Default::default()
+        }
+    }
+
+    pub fn with_exposure_compensation(mut self, exposure_compensation: f64) -> Self {
+        self.exposure_compensation = exposure_compensation;
+        self
+    }
+
+    pub fn with_working_space(mut self, working_space: WorkingSpace) -> Self {
+        self.working_space = working_space;
+        self
+    }
+
+    pub fn apply(&self, color: Color3) -> Color3 {
+        let color = match self.working_space {
+            WorkingSpace::Rec709 => color,
+            WorkingSpace::AcesCg => transform(&ACESCG_TO_REC709, color),
+        };
+        self.tone_map
+            .apply(2_f64.powf(self.exposure_compensation) * color)
+    }
+
+    pub fn apply_image(&self, image: &Image) -> Image {
+        let mut display = image.clone();
+        for pixel in display.pixels_mut() {
+            *pixel = self.apply(*pixel);
+        }
+        display
+    }
+}
+
+/// The sRGB opto-electronic transfer function, from linear [0,1] to encoded [0,1].
+pub fn srgb_oetf(linear: f64) -> f64 {
+    match linear <= 0.0031308 {
+        true => 12.92 * linear,
+        false => 1.055 * linear.powf(1. / 2.4) - 0.055,
+    }
+}
+
+/// Inverse of [`srgb_oetf`], from encoded [0,1] to linear [0,1].
+pub fn srgb_eotf(encoded: f64) -> f64 {
+    match encoded <= 0.04045 {
+        true => encoded / 12.92,
+        false => ((encoded + 0.055) / 1.055).powf(2.4),
+    }
+}
+
+fn transform(m: &Matrix, c: Color3) -> Color3 {
+    Color3::new(
+        m[0][0] * c.x + m[0][1] * c.y + m[0][2] * c.z,
+        m[1][0] * c.x + m[1][1] * c.y + m[1][2] * c.z,
+        m[2][0] * c.x + m[2][1] * c.y + m[2][2] * c.z,
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn srgb_oetf() {
+        // Arrange
+        let linear = [0., 0.0031308, 0.18, 1.];
+
+        // Act
+        let encoded = linear.map(super::srgb_oetf);
+
+        // Assert
+        assert_eq!(encoded[0], 0.);
+        assert!((encoded[1] - 0.04045).abs() < 1e-5);
+        assert!((encoded[2] - 0.4614).abs() < 1e-4);
+        assert!((encoded[3] - 1.).abs() < 1e-12);
+    }
+
+    #[test]
+    fn tone_map() {
+        // Arrange
+        let levels = [0., 0.01, 0.18, 1., 4., 16., 1000.];
+
+        for tone_map in [ToneMap::Reinhard, ToneMap::AcesFilmic, ToneMap::Agx] {
+            // Act
+            let mapped = levels.map(|l| tone_map.apply(Color3::new(l, l, l)).y);
+
+            // Assert
+            assert!(mapped[0] < 0.01, "{tone_map:?} lifts black");
+            assert!(
+                mapped.windows(2).all(|w| w[0] <= w[1]),
+                "{tone_map:?} isn't monotonic"
+            );
+            assert!(mapped[4] < 1., "{tone_map:?} clips highlights");
+            assert!(mapped[6] > 0.9, "{tone_map:?} doesn't reach white");
+        }
+    }
+
+    #[test]
+    fn acescg_to_rec709() {
+        // Arrange
+        let display = DisplayTransform::default().with_working_space(WorkingSpace::AcesCg);
+        let gray = Color3::new(0.18, 0.18, 0.18);
+        let red = Color3::new(1., 0., 0.);
+
+        // Act
+        let gray = display.apply(gray);
+        let red = transform(&ACESCG_TO_REC709, red);
+
+        // Assert
+        // Both have the same white, up to the rounding of the matrix.
+        assert!(
+            (gray - Color3::new(0.18, 0.18, 0.18)).length() < 1e-5,
+            "{gray:?}"
+        );
+        // The AP1 red primary lies outside Rec. 709.
+        assert!(
+            (red - Color3::new(1.70505, -0.13026, -0.02400)).near_zero(),
+            "{red:?}"
+        );
+    }
+}
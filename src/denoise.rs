@@ -0,0 +1,161 @@
+use crate::{image::Image, vec3::Color3};
+
+/// Weights of the B3-spline used as the à-trous kernel along each axis.
+const KERNEL: [f64; 5] = [1. / 16., 1. / 4., 3. / 8., 1. / 4., 1. / 16.];
+
+/// Luminance added when comparing colors, so noise in black regions is still smoothed.
+const MIN_LUMINANCE: f64 = 0.01;
+
+/// Smallest albedo the beauty image is divided by, to keep black surfaces finite.
+const MIN_ALBEDO: f64 = 0.01;
+
+/// Edge-avoiding à-trous wavelet filter, after Dammertz et al., "Edge-Avoiding À-Trous
+/// Wavelet Transform for fast Global Illumination Filtering".
+///
+/// The beauty image is divided by the albedo, so textures stay sharp, and then blurred with
+/// a kernel that widens each iteration. Weights fall off with differences in color, normal
+/// and albedo, so edges between surfaces are kept.
+#[derive(Clone, Copy, Debug)]
+pub struct Denoiser {
+    /// Count of filter passes; the kernel covers `4 * 2^iterations + 1` pixels
+    pub iterations: u32,
+    pub sigma_albedo: f64,
+    /// Color difference scale in the first pass, halved in every later pass
+    pub sigma_color: f64,
+    pub sigma_normal: f64,
+}
+
+impl Default for Denoiser {
+    fn default() -> Self {
+        Self {
+            iterations: 5,
+            sigma_albedo: 0.1,
+            sigma_color: 1.,
+            sigma_normal: 0.3,
+        }
+    }
+}
+
+impl Denoiser {
+    pub fn new(iterations: u32) -> Self {
+        Self {
+            iterations,
+            ..Default::default()
+        }
+    }
+
+    /// Returns the denoised beauty image, given the albedo and normal images of the same
+    /// size.
+    pub fn denoise(&self, beauty: &Image, albedo: &Image, normal: &Image) -> Image {
+        let (width, height) = (beauty.width() as i64, beauty.height() as i64);
+        let divisor = |a: Color3| {
+            Color3::new(
+                a.x.max(MIN_ALBEDO),
+                a.y.max(MIN_ALBEDO),
+                a.z.max(MIN_ALBEDO),
+            )
+        };
+        let mut color = beauty
+            .pixels()
+            .iter()
+            .zip(albedo.pixels())
+            .map(|(&c, &a)| c / divisor(a))
+            .collect::<Vec<_>>();
+        let (albedo, normal) = (albedo.pixels(), normal.pixels());
+
+        for iteration in 0..self.iterations {
+            let step = 1_i64 << iteration;
+            let sigma_color = self.sigma_color / step as f64;
+            let mut filtered = vec![Color3::default(); color.len()];
+
+            for j in 0..height {
+                for i in 0..width {
+                    let p = (j * width + i) as usize;
+                    let mut sum = Color3::default();
+                    let mut weight_sum = 0.;
+
+                    for (dy, ky) in (-2..=2).zip(KERNEL) {
+                        for (dx, kx) in (-2..=2).zip(KERNEL) {
+                            let (x, y) = (i + dx * step, j + dy * step);
+                            if !(0..width).contains(&x) || !(0..height).contains(&y) {
+                                continue;
+                            }
+
+                            let q = (y * width + x) as usize;
+                            let weight = kx
+                                * ky
+                                * edge_stop(relative(color[p], color[q]), sigma_color)
+                                * edge_stop(normal[p] - normal[q], self.sigma_normal)
+                                * edge_stop(albedo[p] - albedo[q], self.sigma_albedo);
+                            sum += weight * color[q];
+                            weight_sum += weight;
+                        }
+                    }
+
+                    // The center pixel always has a positive weight.
+                    filtered[p] = sum / weight_sum;
+                }
+            }
+            color = filtered;
+        }
+
+        let mut image = Image::new(beauty.width(), beauty.height());
+        for ((pixel, c), &a) in image.pixels_mut().iter_mut().zip(color).zip(albedo) {
+            *pixel = c * divisor(a);
+        }
+        image
+    }
+}
+
+/// Returns the difference of two colors relative to their brightness, so bright and dark
+/// regions are smoothed alike.
+fn relative(a: Color3, b: Color3) -> Color3 {
+    (a - b) / (a.luminance() + b.luminance() + MIN_LUMINANCE)
+}
+
+fn edge_stop(difference: Color3, sigma: f64) -> f64 {
+    (-difference.length_squared() / (sigma * sigma)).exp()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::util::{random_double, seed_random};
+
+    #[test]
+    fn denoise() {
+        // Arrange
+        seed_random(1);
+        let (width, height) = (32, 32);
+        let mut beauty = Image::new(width, height);
+        let mut normal = Image::new(width, height);
+        let mut albedo = Image::new(width, height);
+        for j in 0..height {
+            for i in 0..width {
+                // Two differently lit surfaces meet at the middle column.
+                let (level, n) = match i < width / 2 {
+                    true => (0.2, Color3::new(0., 1., 0.)),
+                    false => (0.8, Color3::new(1., 0., 0.)),
+                };
+                let noisy = level * (0.5 + random_double());
+                beauty.set(i, j, Color3::new(noisy, noisy, noisy));
+                normal.set(i, j, n);
+                albedo.set(i, j, Color3::new(0.5, 0.5, 0.5));
+            }
+        }
+
+        // Act
+        let denoised = Denoiser::default().denoise(&beauty, &albedo, &normal);
+
+        // Assert
+        for j in 0..height {
+            for i in 0..width {
+                let expected = match i < width / 2 {
+                    true => 0.2,
+                    false => 0.8,
+                };
+                assert!((denoised.get(i, j).x - expected).abs() < 0.1 * expected);
+            }
+        }
+    }
+}
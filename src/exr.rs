@@ -0,0 +1,109 @@
+//! Writer for uncompressed, scanline OpenEXR files with 32-bit float channels.
+
+use std::io::{self, Write};
+
+/// Write an OpenEXR image with the named channels, each holding `width * height` values in
+/// row-major order starting at the upper left pixel.
+pub fn write_exr(
+    out: &mut impl Write,
+    width: u32,
+    height: u32,
+    channels: &[(&str, Vec<f32>)],
+) -> io::Result<()> {
+    // Readers expect the channels sorted by name.
+    let mut channels = channels.iter().collect::<Vec<_>>();
+    channels.sort_by_key(|(name, _)| *name);
+
+    let mut header = Vec::new();
+    header.extend_from_slice(&[0x76, 0x2f, 0x31, 0x01]); // magic number
+    header.extend_from_slice(&2_u32.to_le_bytes()); // version 2, single-part scanline
+
+    let mut chlist = Vec::new();
+    for (name, _) in &channels {
+        chlist.extend_from_slice(name.as_bytes());
+        chlist.push(0);
+        chlist.extend_from_slice(&2_i32.to_le_bytes()); // FLOAT
+        chlist.extend_from_slice(&[0, 0, 0, 0]); // pLinear and reserved
+        chlist.extend_from_slice(&1_i32.to_le_bytes()); // xSampling
+        chlist.extend_from_slice(&1_i32.to_le_bytes()); // ySampling
+    }
+    chlist.push(0);
+    attribute(&mut header, "channels", "chlist", &chlist);
+    attribute(&mut header, "compression", "compression", &[0]);
+
+    let mut window = Vec::new();
+    for v in [0, 0, width as i32 - 1, height as i32 - 1] {
+        window.extend_from_slice(&v.to_le_bytes());
+    }
+    attribute(&mut header, "dataWindow", "box2i", &window);
+    attribute(&mut header, "displayWindow", "box2i", &window);
+    attribute(&mut header, "lineOrder", "lineOrder", &[0]); // increasing y
+    attribute(
+        &mut header,
+        "pixelAspectRatio",
+        "float",
+        &1_f32.to_le_bytes(),
+    );
+    attribute(&mut header, "screenWindowCenter", "v2f", &[0; 8]);
+    attribute(
+        &mut header,
+        "screenWindowWidth",
+        "float",
+        &1_f32.to_le_bytes(),
+    );
+    header.push(0);
+
+    // Every scanline is a chunk of its y coordinate, its size and the channel values.
+    let line_size = (width as usize) * channels.len() * 4;
+    let chunk_size = 8 + line_size as u64;
+    let table_end = (header.len() + 8 * height as usize) as u64;
+
+    out.write_all(&header)?;
+    for y in 0..u64::from(height) {
+        out.write_all(&(table_end + y * chunk_size).to_le_bytes())?;
+    }
+    for y in 0..height as usize {
+        out.write_all(&(y as i32).to_le_bytes())?;
+        out.write_all(&(line_size as i32).to_le_bytes())?;
+        for (_, values) in &channels {
+            let line = &values[y * width as usize..(y + 1) * width as usize];
+            for v in line {
+                out.write_all(&v.to_le_bytes())?;
+            }
+        }
+    }
+    Ok(())
+}
+
+fn attribute(header: &mut Vec<u8>, name: &str, kind: &str, value: &[u8]) {
+    header.extend_from_slice(name.as_bytes());
+    header.push(0);
+    header.extend_from_slice(kind.as_bytes());
+    header.push(0);
+    header.extend_from_slice(&(value.len() as i32).to_le_bytes());
+    header.extend_from_slice(value);
+}
+
+#[cfg(test)]
+mod tests {
+    #[test]
+    fn write_exr() {
+        // Arrange
+        let channels = [("Z", vec![1.; 6]), ("A", vec![0.5; 6])];
+        let mut out = Vec::new();
+
+        // Act
+        super::write_exr(&mut out, 3, 2, &channels).unwrap();
+
+        // Assert
+        assert_eq!(out[..4], [0x76, 0x2f, 0x31, 0x01]);
+        let header_end = out.len() - 2 * 8 - 2 * (8 + 3 * 2 * 4);
+        let first_offset = u64::from_le_bytes(out[header_end..header_end + 8].try_into().unwrap());
+        assert_eq!(first_offset as usize, header_end + 16);
+        let channel_a = out.windows(3).position(|w| w == b"A\0\x02").unwrap();
+        let channel_z = out.windows(3).position(|w| w == b"Z\0\x02").unwrap();
+        assert!(channel_a < channel_z);
+        let first_value = &out[first_offset as usize + 8..][..4];
+        assert_eq!(f32::from_le_bytes(first_value.try_into().unwrap()), 0.5);
+    }
+}
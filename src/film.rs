@@ -0,0 +1,175 @@
+use crate::{filter::Filter, image::Image, vec3::Color3};
+
+/// Fraction of the absolute weights of a pixel below which its signed weight is too close to
+/// cancelling out to divide by
+const MIN_WEIGHT_FRACTION: f64 = 0.1;
+
+/// Accumulates filter-weighted samples, which may contribute to several pixels each.
+#[derive(Clone, Debug)]
+pub struct Film {
+    width: u32,
+    height: u32,
+    pixels: Vec<FilmPixel>,
+}
+
+#[derive(Clone, Debug, Default)]
+struct FilmPixel {
+    /// Sum of the absolute weights, which negative filter lobes can't cancel
+    abs_weight_sum: f64,
+    weighted_sum: Color3,
+    weight_sum: f64,
+}
+
+impl Film {
+    pub fn new(width: u32, height: u32) -> Self {
+        Self {
+            width,
+            height,
+            pixels: vec![FilmPixel::default(); (width * height) as usize],
+        }
+    }
+
+    /// Splat a sample at raster position `(x, y)` to all pixels within the filter radius.
+    ///
+    /// Pixel i,j covers `[i, i+1) × [j, j+1)` in raster space.
+    pub fn add_sample(&mut self, filter: &dyn Filter, x: f64, y: f64, color: Color3) {
+        let radius = filter.radius();
+        let i_range = self.pixel_range(x, radius, self.width);
+        let j_range = self.pixel_range(y, radius, self.height);
+
+        for j in j_range {
+            for i in i_range.clone() {
+                let weight = filter.evaluate(x - (f64::from(i) + 0.5), y - (f64::from(j) + 0.5));
+                if weight != 0. {
+                    let pixel = &mut self.pixels[(j * self.width + i) as usize];
+                    pixel.weighted_sum += weight * color;
+                    pixel.weight_sum += weight;
+                    pixel.abs_weight_sum += weight.abs();
+                }
+            }
+        }
+    }
+
+    /// Returns the weighted average of the samples of each pixel.
+    ///
+    /// Where negative filter lobes nearly cancel the positive weights, dividing by their sum
+    /// would blow the pixel up, so it is divided by the sum of the absolute weights instead.
+    pub fn image(&self) -> Image {
+        let mut image = Image::new(self.width, self.height);
+        for (color, pixel) in image.pixels_mut().iter_mut().zip(&self.pixels) {
+            if pixel.abs_weight_sum > 0. {
+                let weight =
+                    match pixel.weight_sum.abs() >= MIN_WEIGHT_FRACTION * pixel.abs_weight_sum {
+                        true => pixel.weight_sum,
+                        false => pixel.abs_weight_sum,
+                    };
+                *color = pixel.weighted_sum / weight;
+            }
+        }
+        image
+    }
+
+    /// Returns the pixels along one axis whose filter support contains `v`.
+    fn pixel_range(&self, v: f64, radius: f64, size: u32) -> std::ops::Range<u32> {
+        let first = (v - 0.5 - radius).ceil().max(0.);
+        let last = (v - 0.5 + radius).floor().min(f64::from(size) - 1.);
+        match first <= last {
+            true => first as u32..last as u32 + 1,
+            false => 0..0,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::filter::{BoxFilter, MitchellFilter};
+
+    /// Filter with a positive left half and a slightly weaker negative right half
+    #[derive(Debug)]
+    struct NegativeLobeFilter;
+
+    impl Filter for NegativeLobeFilter {
+        fn radius(&self) -> f64 {
+            0.5
+        }
+
+        fn evaluate(&self, x: f64, _y: f64) -> f64 {
+            match x < 0. {
+                true => 1.,
+                false => -0.999_999,
+            }
+        }
+    }
+
+    #[test]
+    fn add_sample_box() {
+        // Arrange
+        let mut film = Film::new(3, 3);
+
+        // Act
+        film.add_sample(&BoxFilter::default(), 1., 1.5, Color3::new(1., 1., 1.));
+
+        // Assert
+        let image = film.image();
+        for j in 0..3 {
+            for i in 0..3 {
+                let result = match (i, j) {
+                    (1, 1) => Color3::new(1., 1., 1.),
+                    _ => Color3::default(),
+                };
+                assert_eq!(image.get(i, j), result);
+            }
+        }
+    }
+
+    #[test]
+    fn add_sample_constant() {
+        // Arrange
+        let mut film = Film::new(4, 4);
+        let filter = MitchellFilter::new(2., 1. / 3., 1. / 3.);
+
+        // Act
+        for j in 0..8 {
+            for i in 0..8 {
+                let (x, y) = ((f64::from(i) + 0.5) / 2., (f64::from(j) + 0.5) / 2.);
+                film.add_sample(&filter, x, y, Color3::new(0.5, 0.5, 0.5));
+            }
+        }
+
+        // Assert
+        for color in film.image().pixels() {
+            assert!((*color - Color3::new(0.5, 0.5, 0.5)).near_zero());
+        }
+    }
+
+    #[test]
+    fn add_sample_cancelling() {
+        // Arrange
+        let mut film = Film::new(1, 1);
+        let filter = NegativeLobeFilter;
+
+        // Act
+        film.add_sample(&filter, 0.25, 0.5, Color3::new(1., 1., 1.));
+        film.add_sample(&filter, 0.75, 0.5, Color3::new(0.5, 0.5, 0.5));
+
+        // Assert
+        let color = film.image().get(0, 0);
+        assert!(color.x.abs() <= 1., "{color:?}");
+    }
+
+    #[test]
+    fn add_sample_negative_lobe() {
+        // Arrange
+        let mut film = Film::new(3, 1);
+        let filter = MitchellFilter::new(2., 1. / 3., 1. / 3.);
+
+        // Act
+        // Only in the negative lobe of pixel 0
+        film.add_sample(&filter, 2.2, 0.5, Color3::new(0.5, 0.5, 0.5));
+
+        // Assert
+        assert!(filter.evaluate(2.2 - 0.5, 0.) < 0.);
+        assert!((film.image().get(0, 0) - Color3::new(0.5, 0.5, 0.5)).near_zero());
+    }
+}
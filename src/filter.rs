@@ -0,0 +1,217 @@
+//! Pixel reconstruction filters.
+//!
+//! Offsets are in pixels from the pixel center. Filters are separable and zero outside of
+//! `radius` in both directions.
+
+use std::{f64::consts::PI, fmt::Debug};
+
+pub trait Filter: Debug {
+    fn radius(&self) -> f64;
+
+    /// Returns the weight of a sample at offset `(x, y)` from the pixel center.
+    fn evaluate(&self, x: f64, y: f64) -> f64;
+}
+
+/// Equal weight for all samples within the radius.
+#[derive(Debug)]
+pub struct BoxFilter {
+    radius: f64,
+}
+
+impl BoxFilter {
+    pub fn new(radius: f64) -> Self {
+        Self { radius }
+    }
+}
+
+impl Default for BoxFilter {
+    /// A box covering exactly one pixel.
+    fn default() -> Self {
+        Self::new(0.5)
+    }
+}
+
+impl Filter for BoxFilter {
+    fn radius(&self) -> f64 {
+        self.radius
+    }
+
+    fn evaluate(&self, x: f64, y: f64) -> f64 {
+        // Half-open, so a sample on the border between two pixels only counts once.
+        let inside = |v: f64| (-self.radius..self.radius).contains(&v);
+        match inside(x) && inside(y) {
+            true => 1.,
+            false => 0.,
+        }
+    }
+}
+
+/// Weight falling off linearly to zero at the radius.
+#[derive(Debug)]
+pub struct TentFilter {
+    radius: f64,
+}
+
+impl TentFilter {
+    pub fn new(radius: f64) -> Self {
+        Self { radius }
+    }
+}
+
+impl Filter for TentFilter {
+    fn radius(&self) -> f64 {
+        self.radius
+    }
+
+    fn evaluate(&self, x: f64, y: f64) -> f64 {
+        (self.radius - x.abs()).max(0.) * (self.radius - y.abs()).max(0.)
+    }
+}
+
+/// Gaussian with standard deviation `sigma`, shifted down to reach zero at the radius.
+#[derive(Debug)]
+pub struct GaussianFilter {
+    radius: f64,
+    sigma: f64,
+}
+
+impl GaussianFilter {
+    pub fn new(radius: f64, sigma: f64) -> Self {
+        Self { radius, sigma }
+    }
+
+    fn gaussian(&self, x: f64) -> f64 {
+        (-x * x / (2. * self.sigma * self.sigma)).exp()
+    }
+}
+
+impl Filter for GaussianFilter {
+    fn radius(&self) -> f64 {
+        self.radius
+    }
+
+    fn evaluate(&self, x: f64, y: f64) -> f64 {
+        let edge = self.gaussian(self.radius);
+        let g = |v: f64| (self.gaussian(v) - edge).max(0.);
+        g(x) * g(y)
+    }
+}
+
+/// Mitchell–Netravali cubic with parameters `b` and `c`; `b = c = 1/3` is the recommended
+/// trade-off between ringing and blurring.
+#[derive(Debug)]
+pub struct MitchellFilter {
+    radius: f64,
+    b: f64,
+    c: f64,
+}
+
+impl MitchellFilter {
+    pub fn new(radius: f64, b: f64, c: f64) -> Self {
+        Self { radius, b, c }
+    }
+
+    /// The cubic with support [-2,2].
+    fn mitchell_1d(&self, x: f64) -> f64 {
+        let (b, c) = (self.b, self.c);
+        let x = x.abs();
+        let v = if x <= 1. {
+            (12. - 9. * b - 6. * c) * x.powi(3)
+                + (-18. + 12. * b + 6. * c) * x.powi(2)
+                + (6. - 2. * b)
+        } else if x < 2. {
+            (-b - 6. * c) * x.powi(3)
+                + (6. * b + 30. * c) * x.powi(2)
+                + (-12. * b - 48. * c) * x
+                + (8. * b + 24. * c)
+        } else {
+            0.
+        };
+        v / 6.
+    }
+}
+
+impl Filter for MitchellFilter {
+    fn radius(&self) -> f64 {
+        self.radius
+    }
+
+    fn evaluate(&self, x: f64, y: f64) -> f64 {
+        self.mitchell_1d(2. * x / self.radius) * self.mitchell_1d(2. * y / self.radius)
+    }
+}
+
+/// Sinc windowed by a wider sinc reaching its first zero at the radius.
+#[derive(Debug)]
+pub struct LanczosFilter {
+    radius: f64,
+}
+
+impl LanczosFilter {
+    pub fn new(radius: f64) -> Self {
+        Self { radius }
+    }
+
+    fn lanczos_1d(&self, x: f64) -> f64 {
+        match x.abs() < self.radius {
+            true => sinc(x) * sinc(x / self.radius),
+            false => 0.,
+        }
+    }
+}
+
+impl Filter for LanczosFilter {
+    fn radius(&self) -> f64 {
+        self.radius
+    }
+
+    fn evaluate(&self, x: f64, y: f64) -> f64 {
+        self.lanczos_1d(x) * self.lanczos_1d(y)
+    }
+}
+
+/// Normalized sinc, `sin(πx) / πx`.
+fn sinc(x: f64) -> f64 {
+    match x.abs() < 1e-5 {
+        true => 1.,
+        false => (PI * x).sin() / (PI * x),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn support_and_peak() {
+        // Arrange
+        let radius = 1.5;
+        let filters: [Box<dyn Filter>; 5] = [
+            Box::new(BoxFilter::new(radius)),
+            Box::new(TentFilter::new(radius)),
+            Box::new(GaussianFilter::new(radius, 0.5)),
+            Box::new(MitchellFilter::new(radius, 1. / 3., 1. / 3.)),
+            Box::new(LanczosFilter::new(radius)),
+        ];
+        let offsets = (-20..=20).map(|i| f64::from(i) / 10.);
+        // The box is half-open, so only the positive side includes the radius.
+        let inside = |v: f64| (-radius..radius).contains(&v);
+
+        for filter in filters {
+            // Act
+            let center = filter.evaluate(0., 0.);
+
+            // Assert
+            assert!(center > 0., "{filter:?}");
+            for x in offsets.clone() {
+                for y in offsets.clone() {
+                    let weight = filter.evaluate(x, y);
+                    match inside(x) && inside(y) {
+                        true => assert!(weight <= center, "{filter:?} at {x},{y}"),
+                        false => assert_eq!(weight, 0., "{filter:?} at {x},{y}"),
+                    }
+                }
+            }
+        }
+    }
+}
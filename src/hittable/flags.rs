@@ -0,0 +1,64 @@
+use crate::ray::RayKind;
+
+/// Per-object render settings, for look development.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct ObjectFlags {
+    /// Seen by rays from the camera
+    pub camera: bool,
+    /// Rendered black where the camera sees it, leaving a hole to composite other footage
+    /// into; it still shows up in reflections and casts shadows
+    pub holdout: bool,
+    /// Objects lit by the object, if it is emissive
+    pub light_link: LightLink,
+    /// Seen in reflections off mirrors, glossy surfaces and glass
+    pub reflection: bool,
+    /// Seen through glass
+    pub refraction: bool,
+    /// Blocks the light from lights and the background on its way to other surfaces, and
+    /// occlusion rays; the bidirectional path tracer ignores it
+    pub shadow: bool,
+}
+
+impl Default for ObjectFlags {
+    fn default() -> Self {
+        Self {
+            camera: true,
+            holdout: false,
+            light_link: LightLink::default(),
+            reflection: true,
+            refraction: true,
+            shadow: true,
+        }
+    }
+}
+
+impl ObjectFlags {
+    /// Whether rays of the given kind can hit the object.
+    pub fn visible_to(&self, kind: RayKind) -> bool {
+        match kind {
+            RayKind::Camera => self.camera,
+            RayKind::Diffuse => true,
+            RayKind::Reflection => self.reflection,
+            RayKind::Refraction => self.refraction,
+        }
+    }
+}
+
+/// Objects a light illuminates, by their IDs in the scene.
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub enum LightLink {
+    #[default]
+    All,
+    Include(Vec<u32>),
+    Exclude(Vec<u32>),
+}
+
+impl LightLink {
+    pub fn lights(&self, object_id: u32) -> bool {
+        match self {
+            LightLink::All => true,
+            LightLink::Include(ids) => ids.contains(&object_id),
+            LightLink::Exclude(ids) => !ids.contains(&object_id),
+        }
+    }
+}
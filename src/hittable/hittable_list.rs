@@ -1,8 +1,20 @@
-use crate::{hittable::Hittable, interval::Interval};
+use crate::{
+    hittable::{HitRecord, Hittable, ObjectFlags},
+    interval::Interval,
+    ray::Ray,
+    util::random_double,
+    vec3::{Point3, Vec3},
+};
+
+struct Entry<'a> {
+    flags: ObjectFlags,
+    id: u32,
+    object: Box<dyn Hittable + 'a>,
+}
 
 #[derive(Default)]
 pub struct HittableList<'a> {
-    objects: Vec<Box<dyn Hittable + 'a>>,
+    objects: Vec<Entry<'a>>,
 }
 
 impl<'a> HittableList<'a> {
@@ -14,22 +26,76 @@ impl<'a> HittableList<'a> {
         self.objects.clear()
     }
 
-    pub fn add<T>(&mut self, object: T)
+    pub fn is_empty(&self) -> bool {
+        self.objects.is_empty()
+    }
+
+    /// Add an object with the default flags, returning its ID.
+    pub fn add<T>(&mut self, object: T) -> u32
     where
         T: Hittable + 'a,
     {
-        self.objects.push(Box::new(object))
+        self.add_with_flags(object, ObjectFlags::default())
     }
-}
 
-impl Hittable for HittableList<'_> {
-    fn hit(&self, r: &crate::ray::Ray, ray_t: &Interval) -> Option<crate::hittable::HitRecord> {
+    /// Add an object, returning its ID, which is its position in the list counting from one.
+    pub fn add_with_flags<T>(&mut self, object: T, flags: ObjectFlags) -> u32
+    where
+        T: Hittable + 'a,
+    {
+        let id = self.objects.len() as u32 + 1;
+        self.add_entry(id, object, flags);
+        id
+    }
+
+    /// Add an object under an ID given elsewhere, e.g. a light under its ID in the world.
+    pub(crate) fn add_entry<T>(&mut self, id: u32, object: T, flags: ObjectFlags)
+    where
+        T: Hittable + 'a,
+    {
+        self.objects.push(Entry {
+            flags,
+            id,
+            object: Box::new(object),
+        })
+    }
+
+    pub fn flags(&self, id: u32) -> Option<&ObjectFlags> {
+        // IDs given by the list itself are positions, so try that first.
+        let position = (id as usize).checked_sub(1);
+        position
+            .and_then(|i| self.objects.get(i))
+            .filter(|entry| entry.id == id)
+            .or_else(|| self.objects.iter().find(|entry| entry.id == id))
+            .map(|entry| &entry.flags)
+    }
+
+    pub fn has_holdouts(&self) -> bool {
+        self.objects.iter().any(|entry| entry.flags.holdout)
+    }
+
+    /// Returns the closest hit with the objects that cast shadows.
+    pub fn hit_shadowing(&self, r: &Ray, ray_t: &Interval) -> Option<HitRecord> {
+        self.closest_hit(r, ray_t, |entry| entry.flags.shadow)
+    }
+
+    /// Returns the closest hit with the objects visible to `r` for which `include` holds.
+    fn closest_hit(
+        &self,
+        r: &Ray,
+        ray_t: &Interval,
+        include: impl Fn(&Entry) -> bool,
+    ) -> Option<HitRecord> {
         let mut hit_anything = None;
         let mut ray_t = ray_t.clone();
 
-        for o in &self.objects {
-            if let Some(rec) = o.hit(r, &ray_t) {
+        for entry in &self.objects {
+            if !entry.flags.visible_to(r.kind) || !include(entry) {
+                continue;
+            }
+            if let Some(mut rec) = entry.object.hit(r, &ray_t) {
                 ray_t.max = rec.t;
+                rec.object_id = entry.id;
                 hit_anything = Some(rec);
             }
         }
@@ -37,3 +103,100 @@ impl Hittable for HittableList<'_> {
         hit_anything
     }
 }
+
+impl Hittable for HittableList<'_> {
+    /// Skips the objects hidden from rays of `r`'s kind.
+    fn hit(&self, r: &Ray, ray_t: &Interval) -> Option<HitRecord> {
+        self.closest_hit(r, ray_t, |_| true)
+    }
+
+    /// Skips the objects that don't cast shadows.
+    fn occluded(&self, r: &Ray, ray_t: &Interval) -> bool {
+        self.objects
+            .iter()
+            .any(|entry| entry.flags.shadow && entry.object.occluded(r, ray_t))
+    }
+
+    /// Returns the density of picking one of the objects at random and sampling it.
+    fn pdf_value(&self, origin: &Point3, direction: &Vec3) -> f64 {
+        let weight = 1. / self.objects.len() as f64;
+        self.objects
+            .iter()
+            .map(|entry| weight * entry.object.pdf_value(origin, direction))
+            .sum()
+    }
+
+    /// Sample one of the objects picked at random, or none if the pick can't be sampled,
+    /// which keeps [`Hittable::pdf_value`] the density of the directions returned.
+    fn random(&self, origin: &Point3) -> Option<Vec3> {
+        let index = (random_double() * self.objects.len() as f64) as usize;
+        self.objects
+            .get(index.min(self.objects.len().checked_sub(1)?))?
+            .object
+            .random(origin)
+    }
+
+    /// Pick one of the objects with the first sample dimension, reusing what is left of it.
+    fn sample_surface(&self, u: (f64, f64)) -> Option<(HitRecord, f64)> {
+        let n = self.objects.len() as f64;
+        let index = ((u.0 * n) as usize).min(self.objects.len().checked_sub(1)?);
+        let rest = (u.0 * n - index as f64).min(1. - f64::EPSILON);
+        let entry = &self.objects[index];
+        let (mut rec, pdf) = entry.object.sample_surface((rest, u.1))?;
+        rec.object_id = entry.id;
+        Some((rec, pdf / n))
+    }
+
+    fn surface_pdf(&self, p: &Point3) -> f64 {
+        let weight = 1. / self.objects.len() as f64;
+        self.objects
+            .iter()
+            .map(|entry| weight * entry.object.surface_pdf(p))
+            .sum()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::rc::Rc;
+
+    use super::*;
+    use crate::{
+        hittable::Sphere,
+        material::{Lambertian, Material},
+        vec3::Color3,
+    };
+
+    #[test]
+    fn occluded() {
+        // Arrange
+        let material: Rc<dyn Material> = Rc::new(Lambertian::new(Color3::new(0.5, 0.5, 0.5)));
+        let mut list = HittableList::new();
+        for z in [-2., -4.] {
+            list.add(Sphere::new(
+                Point3::new(0., 0., z),
+                0.5,
+                Rc::clone(&material) as _,
+            ));
+        }
+        let r = Ray::new(Point3::new(0., 0., 0.), Vec3::new(0., 0., -1.));
+        // The spheres are hit at t = 1.5 and t = 3.5, so the last intervals end before
+        // either and lie between them.
+        let intervals = [
+            Interval::new(0.001, f64::INFINITY),
+            Interval::new(2.6, 5.),
+            Interval::new(0.001, 1.4),
+            Interval::new(2.6, 3.4),
+        ];
+
+        // Act
+        let results =
+            intervals.map(|ray_t| (list.occluded(&r, &ray_t), list.hit(&r, &ray_t).is_some()));
+
+        // Assert
+        assert_eq!(
+            results,
+            [(true, true), (true, true), (false, false), (false, false)]
+        );
+    }
+}
@@ -1,5 +1,7 @@
+mod flags;
 mod hittable_list;
 mod sphere;
+mod triangle;
 
 use std::rc::Rc;
 
@@ -10,16 +12,78 @@ use crate::{
     vec3::{Point3, Vec3},
 };
 
-pub use self::{hittable_list::HittableList, sphere::Sphere};
+pub use self::{
+    flags::{LightLink, ObjectFlags},
+    hittable_list::HittableList,
+    sphere::Sphere,
+    triangle::Triangle,
+};
 
 pub trait Hittable {
     fn hit(&self, r: &Ray, ray_t: &Interval) -> Option<HitRecord>;
+
+    /// Whether the ray hits anything within `ray_t`, which is cheaper than finding the
+    /// closest hit, e.g. for shadow rays.
+    fn occluded(&self, r: &Ray, ray_t: &Interval) -> bool {
+        self.hit(r, ray_t).is_some()
+    }
+
+    /// Returns the density, over solid angle, of [`Hittable::random`] choosing `direction`
+    /// from `origin`.
+    fn pdf_value(&self, _origin: &Point3, _direction: &Vec3) -> f64 {
+        0.
+    }
+
+    /// Returns a random direction from `origin` towards the object, for sampling it as a
+    /// light, or `None` if the object can't be sampled.
+    fn random(&self, _origin: &Point3) -> Option<Vec3> {
+        None
+    }
+
+    /// Returns a point on the surface for the sample `u`, as a front-facing hit with the
+    /// outward normal, and its density over surface area.
+    fn sample_surface(&self, _u: (f64, f64)) -> Option<(HitRecord, f64)> {
+        None
+    }
+
+    /// Returns the density over surface area of [`Hittable::sample_surface`] choosing `p`.
+    fn surface_pdf(&self, _p: &Point3) -> f64 {
+        0.
+    }
+}
+
+impl<T: Hittable + ?Sized> Hittable for Rc<T> {
+    fn hit(&self, r: &Ray, ray_t: &Interval) -> Option<HitRecord> {
+        (**self).hit(r, ray_t)
+    }
+
+    fn occluded(&self, r: &Ray, ray_t: &Interval) -> bool {
+        (**self).occluded(r, ray_t)
+    }
+
+    fn pdf_value(&self, origin: &Point3, direction: &Vec3) -> f64 {
+        (**self).pdf_value(origin, direction)
+    }
+
+    fn random(&self, origin: &Point3) -> Option<Vec3> {
+        (**self).random(origin)
+    }
+
+    fn sample_surface(&self, u: (f64, f64)) -> Option<(HitRecord, f64)> {
+        (**self).sample_surface(u)
+    }
+
+    fn surface_pdf(&self, p: &Point3) -> f64 {
+        (**self).surface_pdf(p)
+    }
 }
 
 pub struct HitRecord {
     pub front_face: bool,
     pub material: Rc<dyn Material>,
     pub normal: Vec3,
+    /// ID of the object in the scene, counting from one
+    pub object_id: u32,
     pub p: Point3,
     pub t: f64,
 }
@@ -33,6 +97,8 @@ impl HitRecord {
             // will be set with .set_face_normal
             front_face: Default::default(),
             normal: Default::default(),
+            // will be set by the enclosing list
+            object_id: 0,
         }
     }
 
@@ -1,12 +1,19 @@
-use std::rc::Rc;
+use std::{f64::consts::PI, rc::Rc};
 
 use crate::{
     hittable::{HitRecord, Hittable},
     interval::Interval,
     material::Material,
-    vec3::Point3,
+    ray::Ray,
+    sampling::{
+        random_2d, uniform_cone, uniform_cone_pdf, uniform_sphere, uniform_sphere_pdf, Onb,
+    },
+    vec3::{Point3, Vec3},
 };
 
+/// Relative distance from the surface within which points count as lying on it.
+const SURFACE_TOLERANCE: f64 = 1e-6;
+
 pub struct Sphere {
     center: Point3,
     radius: f64,
@@ -21,10 +28,17 @@ impl Sphere {
             material,
         }
     }
-}
 
-impl Hittable for Sphere {
-    fn hit(&self, r: &crate::ray::Ray, ray_t: &Interval) -> Option<HitRecord> {
+    /// Returns the cosine of the half-angle of the cone the sphere covers, seen from
+    /// `origin`, or `None` if `origin` is inside the sphere.
+    fn cos_theta_max(&self, origin: &Point3) -> Option<f64> {
+        let distance_squared = (self.center - *origin).length_squared();
+        let sin2_theta_max = self.radius * self.radius / distance_squared;
+        (sin2_theta_max < 1.).then(|| (1. - sin2_theta_max).sqrt())
+    }
+
+    /// Returns the ray parameter of the nearest intersection within `ray_t`.
+    fn root(&self, r: &Ray, ray_t: &Interval) -> Option<f64> {
         let oc = r.origin - self.center;
         let a = r.direction.length_squared();
         let half_b = oc.dot(&r.direction);
@@ -44,11 +58,115 @@ impl Hittable for Sphere {
                 return None;
             }
         }
+        Some(root)
+    }
 
+    fn area(&self) -> f64 {
+        4. * PI * self.radius * self.radius
+    }
+}
+
+impl Hittable for Sphere {
+    fn hit(&self, r: &Ray, ray_t: &Interval) -> Option<HitRecord> {
+        let root = self.root(r, ray_t)?;
         let mut rec = HitRecord::new(Rc::clone(&self.material), r.at(root), root);
         let outward_normal = (rec.p - self.center) / self.radius;
         rec.set_face_normal(r, &outward_normal);
 
         Some(rec)
     }
+
+    fn occluded(&self, r: &Ray, ray_t: &Interval) -> bool {
+        self.root(r, ray_t).is_some()
+    }
+
+    /// Sample the cone of directions towards the sphere uniformly, or all directions from
+    /// inside it.
+    fn pdf_value(&self, origin: &Point3, direction: &Vec3) -> f64 {
+        let ray = Ray::new(*origin, *direction);
+        if self
+            .root(&ray, &Interval::new(0.001, f64::INFINITY))
+            .is_none()
+        {
+            return 0.;
+        }
+        match self.cos_theta_max(origin) {
+            Some(cos_theta_max) => uniform_cone_pdf(cos_theta_max),
+            None => uniform_sphere_pdf(),
+        }
+    }
+
+    fn random(&self, origin: &Point3) -> Option<Vec3> {
+        Some(match self.cos_theta_max(origin) {
+            Some(cos_theta_max) => {
+                Onb::new(&(self.center - *origin)).local(&uniform_cone(random_2d(), cos_theta_max))
+            }
+            None => uniform_sphere(random_2d()),
+        })
+    }
+
+    fn sample_surface(&self, u: (f64, f64)) -> Option<(HitRecord, f64)> {
+        let normal = uniform_sphere(u);
+        let mut rec = HitRecord::new(
+            Rc::clone(&self.material),
+            self.center + self.radius * normal,
+            0.,
+        );
+        rec.front_face = true;
+        rec.normal = normal;
+        Some((rec, 1. / self.area()))
+    }
+
+    fn surface_pdf(&self, p: &Point3) -> f64 {
+        let distance = (*p - self.center).length();
+        match (distance - self.radius).abs() < SURFACE_TOLERANCE * self.radius {
+            true => 1. / self.area(),
+            false => 0.,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{material::Lambertian, vec3::Color3};
+
+    #[test]
+    fn occluded() {
+        // Arrange
+        let material = Rc::new(Lambertian::new(Color3::new(0.5, 0.5, 0.5)));
+        let sphere = Sphere::new(Point3::new(0., 0., -2.), 0.5, material);
+        let rays = [
+            Ray::new(Point3::new(0., 0., 0.), Vec3::new(0., 0., -1.)),
+            Ray::new(Point3::new(0., 0., 0.), Vec3::new(0., 0., 1.)),
+            Ray::new(Point3::new(0., 0., -2.), Vec3::new(1., 0., 0.)),
+        ];
+        // The first ray hits at t = 1.5 and 2.5, and the last interval ends before either.
+        // The ray from the center leaves at t = 0.5.
+        let intervals = [
+            Interval::new(0.001, f64::INFINITY),
+            Interval::new(1.6, 3.),
+            Interval::new(0.001, 1.4),
+        ];
+
+        // Act
+        let results = rays.map(|r| {
+            intervals.clone().map(|ray_t| {
+                (
+                    sphere.occluded(&r, &ray_t),
+                    sphere.hit(&r, &ray_t).is_some(),
+                )
+            })
+        });
+
+        // Assert
+        assert_eq!(
+            results,
+            [
+                [(true, true), (true, true), (false, false)],
+                [(false, false); 3],
+                [(true, true), (false, false), (true, true)],
+            ]
+        );
+    }
 }
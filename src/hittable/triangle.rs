@@ -0,0 +1,218 @@
+use std::rc::Rc;
+
+use crate::{
+    hittable::{HitRecord, Hittable},
+    interval::Interval,
+    material::Material,
+    ray::Ray,
+    sampling::random_2d,
+    vec3::{Point3, Vec3},
+};
+
+/// Distance from the plane within which points count as lying on the triangle, relative
+/// to its size.
+const PLANE_TOLERANCE: f64 = 1e-6;
+/// Sine of the angle between a ray and the plane below which the ray counts as parallel
+const PARALLEL_TOLERANCE: f64 = 1e-12;
+
+pub struct Triangle {
+    a: Point3,
+    /// Edge from `a` to the second vertex
+    ab: Vec3,
+    /// Edge from `a` to the third vertex
+    ac: Vec3,
+    area: f64,
+    /// Product of the lengths of `ab` and `ac`, which scales the intersection determinant
+    edge_scale: f64,
+    material: Rc<dyn Material>,
+    /// Unit normal, facing the side from which the vertices are counterclockwise
+    normal: Vec3,
+}
+
+impl Triangle {
+    pub fn new(a: Point3, b: Point3, c: Point3, material: Rc<dyn Material>) -> Self {
+        let (ab, ac) = (b - a, c - a);
+        let n = ab.cross(&ac);
+        Self {
+            a,
+            ab,
+            ac,
+            area: n.length() / 2.,
+            edge_scale: ab.length() * ac.length(),
+            material,
+            normal: n.unit_vector(),
+        }
+    }
+
+    /// Returns the point for the sample `u`, uniformly distributed over the triangle.
+    fn point(&self, u: (f64, f64)) -> Point3 {
+        // Fold the unit square onto the triangle along its diagonal.
+        let (s, t) = match u.0 + u.1 > 1. {
+            true => (1. - u.0, 1. - u.1),
+            false => u,
+        };
+        self.a + s * self.ab + t * self.ac
+    }
+}
+
+impl Hittable for Triangle {
+    /// Möller–Trumbore intersection.
+    fn hit(&self, r: &Ray, ray_t: &Interval) -> Option<HitRecord> {
+        let p = r.direction.cross(&self.ac);
+        let det = self.ab.dot(&p);
+        if det.abs() <= PARALLEL_TOLERANCE * self.edge_scale * r.direction.length() {
+            return None;
+        }
+
+        let inv_det = 1. / det;
+        let s = r.origin - self.a;
+        let u = s.dot(&p) * inv_det;
+        if !(0. ..=1.).contains(&u) {
+            return None;
+        }
+        let q = s.cross(&self.ab);
+        let v = r.direction.dot(&q) * inv_det;
+        if v < 0. || u + v > 1. {
+            return None;
+        }
+        let t = self.ac.dot(&q) * inv_det;
+        if !ray_t.surrounds(t) {
+            return None;
+        }
+
+        let mut rec = HitRecord::new(Rc::clone(&self.material), r.at(t), t);
+        rec.set_face_normal(r, &self.normal);
+        Some(rec)
+    }
+
+    /// Sample the triangle uniformly by area, converted to solid angle at `origin`.
+    fn pdf_value(&self, origin: &Point3, direction: &Vec3) -> f64 {
+        let ray = Ray::new(*origin, *direction);
+        let Some(rec) = self.hit(&ray, &Interval::new(0.001, f64::INFINITY)) else {
+            return 0.;
+        };
+        let distance_squared = rec.t * rec.t * direction.length_squared();
+        let cosine = (direction.dot(&self.normal) / direction.length()).abs();
+        distance_squared / (cosine * self.area)
+    }
+
+    fn random(&self, origin: &Point3) -> Option<Vec3> {
+        Some(self.point(random_2d()) - *origin)
+    }
+
+    fn sample_surface(&self, u: (f64, f64)) -> Option<(HitRecord, f64)> {
+        let mut rec = HitRecord::new(Rc::clone(&self.material), self.point(u), 0.);
+        rec.front_face = true;
+        rec.normal = self.normal;
+        Some((rec, 1. / self.area))
+    }
+
+    fn surface_pdf(&self, p: &Point3) -> f64 {
+        let ap = *p - self.a;
+        if ap.dot(&self.normal).abs() > PLANE_TOLERANCE * self.area.sqrt() {
+            return 0.;
+        }
+        // Barycentric coordinates of the point in the plane.
+        let n = self.ab.cross(&self.ac);
+        let u = ap.cross(&self.ac).dot(&n) / n.length_squared();
+        let v = self.ab.cross(&ap).dot(&n) / n.length_squared();
+        match u >= 0. && v >= 0. && u + v <= 1. {
+            true => 1. / self.area,
+            false => 0.,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{material::Lambertian, vec3::Color3};
+
+    fn triangle(scale: f64) -> Triangle {
+        let material = Rc::new(Lambertian::new(Color3::new(0.5, 0.5, 0.5)));
+        Triangle::new(
+            Point3::new(0., 0., 0.),
+            Point3::new(scale, 0., 0.),
+            Point3::new(0., scale, 0.),
+            material,
+        )
+    }
+
+    #[test]
+    fn hit() {
+        // Arrange
+        let ray_t = Interval::new(0.001, f64::INFINITY);
+        let down = Vec3::new(0., 0., -1.);
+        let rays = [
+            // Inside
+            Ray::new(Point3::new(0.25, 0.25, 1.), down),
+            // Outside, past the hypotenuse
+            Ray::new(Point3::new(0.75, 0.75, 1.), down),
+            // On the edge from a to b
+            Ray::new(Point3::new(0.5, 0., 1.), down),
+            // From behind
+            Ray::new(Point3::new(0.25, 0.25, -1.), -down),
+            // Parallel to the plane
+            Ray::new(Point3::new(-1., 0.25, 0.), Vec3::new(1., 0., 0.)),
+        ];
+
+        // Act
+        let hits = rays.map(|r| triangle(1.).hit(&r, &ray_t));
+
+        // Assert
+        let [inside, outside, edge, behind, parallel] = hits;
+        let inside = inside.unwrap();
+        assert_eq!(inside.t, 1.);
+        assert_eq!(inside.p, Point3::new(0.25, 0.25, 0.));
+        assert!(inside.front_face);
+        assert_eq!(inside.normal, Vec3::new(0., 0., 1.));
+        assert!(outside.is_none());
+        assert!(edge.is_some());
+        let behind = behind.unwrap();
+        assert!(!behind.front_face);
+        assert_eq!(behind.normal, Vec3::new(0., 0., -1.));
+        assert!(parallel.is_none());
+    }
+
+    #[test]
+    fn hit_scale() {
+        // Arrange
+        let ray_t = Interval::new(0., f64::INFINITY);
+        let scales = [1e-9, 1e9];
+
+        // Act
+        let hits = scales.map(|scale| {
+            let r = Ray::new(
+                Point3::new(0.25 * scale, 0.25 * scale, scale),
+                Vec3::new(0., 0., -scale),
+            );
+            triangle(scale).hit(&r, &ray_t)
+        });
+
+        // Assert
+        for rec in hits {
+            assert_eq!(rec.unwrap().t, 1.);
+        }
+    }
+
+    #[test]
+    fn surface_pdf() {
+        // Arrange
+        let triangle = triangle(2.);
+        let points = [
+            Point3::new(0.5, 0.5, 0.),
+            Point3::new(1.5, 1.5, 0.),
+            Point3::new(0.5, 0.5, 0.1),
+        ];
+
+        // Act
+        let pdfs = points.map(|p| triangle.surface_pdf(&p));
+
+        // Assert
+        assert_eq!(pdfs, [0.5, 0., 0.]);
+        for u in [(0.1, 0.2), (0.9, 0.8)] {
+            let (rec, pdf) = triangle.sample_surface(u).unwrap();
+            assert_eq!(triangle.surface_pdf(&rec.p), pdf);
+        }
+    }
+}
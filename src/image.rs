@@ -0,0 +1,254 @@
+use std::{
+    fs::{self, File},
+    io::{self, BufWriter, Write},
+    ops::Range,
+    path::Path,
+};
+
+use crate::vec3::Color3;
+
+/// Buffer of linear colors in row-major order, starting at the upper left pixel.
+#[derive(Clone, Debug)]
+pub struct Image {
+    width: u32,
+    height: u32,
+    pixels: Vec<Color3>,
+}
+
+impl Image {
+    pub fn new(width: u32, height: u32) -> Self {
+        Self {
+            width,
+            height,
+            pixels: vec![Color3::default(); width as usize * height as usize],
+        }
+    }
+
+    pub fn width(&self) -> u32 {
+        self.width
+    }
+
+    pub fn height(&self) -> u32 {
+        self.height
+    }
+
+    pub fn get(&self, i: u32, j: u32) -> Color3 {
+        self.pixels[self.index(i, j)]
+    }
+
+    pub fn set(&mut self, i: u32, j: u32, color: Color3) {
+        let index = self.index(i, j);
+        self.pixels[index] = color;
+    }
+
+    pub fn pixels(&self) -> &[Color3] {
+        &self.pixels
+    }
+
+    pub fn pixels_mut(&mut self) -> &mut [Color3] {
+        &mut self.pixels
+    }
+
+    /// Returns the pixels within `x` × `y` as a new image.
+    pub fn crop(&self, x: Range<u32>, y: Range<u32>) -> Self {
+        let mut image = Self::new(x.len() as _, y.len() as _);
+        for (j_out, j) in y.enumerate() {
+            for (i_out, i) in x.clone().enumerate() {
+                image.set(i_out as _, j_out as _, self.get(i, j));
+            }
+        }
+        image
+    }
+
+    /// Copy `image` into this one, with its upper left pixel at i,j.
+    pub fn paste(&mut self, i: u32, j: u32, image: &Image) {
+        for y in 0..image.height {
+            for x in 0..image.width {
+                self.set(i + x, j + y, image.get(x, y));
+            }
+        }
+    }
+
+    /// Write the image as plain PPM.
+    pub fn write_ppm(&self, out: &mut impl Write) -> io::Result<()> {
+        writeln!(out, "P3\n{} {}\n255", self.width, self.height)?;
+        for pixel in &self.pixels {
+            pixel.write_color(out)?;
+        }
+        Ok(())
+    }
+
+    /// Write the linear colors as PFM, without any clamping or transform.
+    pub fn write_pfm(&self, out: &mut impl Write) -> io::Result<()> {
+        // A negative scale marks little-endian values; rows go from bottom to top.
+        write!(out, "PF\n{} {}\n-1.0\n", self.width, self.height)?;
+        for row in self.pixels.chunks_exact(self.width as _).rev() {
+            for pixel in row {
+                for v in [pixel.x, pixel.y, pixel.z] {
+                    out.write_all(&(v as f32).to_le_bytes())?;
+                }
+            }
+        }
+        Ok(())
+    }
+
+    /// Write the linear colors as PFM into the file at `path`.
+    pub fn save_pfm(&self, path: impl AsRef<Path>) -> io::Result<()> {
+        let mut out = BufWriter::new(File::create(path)?);
+        self.write_pfm(&mut out)?;
+        out.flush()
+    }
+
+    /// Write the image as plain PPM into the file at `path`.
+    pub fn save_ppm(&self, path: impl AsRef<Path>) -> io::Result<()> {
+        let mut out = BufWriter::new(File::create(path)?);
+        self.write_ppm(&mut out)?;
+        out.flush()
+    }
+
+    /// Read a PGM or PPM image, in plain or raw format, from the file at `path`.
+    ///
+    /// Values are scaled to [0,1] but not converted back from gamma to linear.
+    pub fn load_pnm(path: impl AsRef<Path>) -> io::Result<Self> {
+        Self::read_pnm(&fs::read(path)?)
+    }
+
+    /// Read a PGM or PPM image, in plain or raw format.
+    ///
+    /// Values are scaled to [0,1] but not converted back from gamma to linear.
+    pub fn read_pnm(data: &[u8]) -> io::Result<Self> {
+        let invalid = |msg: &str| io::Error::new(io::ErrorKind::InvalidData, msg.to_owned());
+
+        // The header is four whitespace separated tokens, with comments starting at `#`.
+        let mut pos = 0;
+        let mut header = Vec::with_capacity(4);
+        while header.len() < 4 {
+            while pos < data.len() && (data[pos].is_ascii_whitespace() || data[pos] == b'#') {
+                if data[pos] == b'#' {
+                    while pos < data.len() && data[pos] != b'\n' {
+                        pos += 1;
+                    }
+                } else {
+                    pos += 1;
+                }
+            }
+            let start = pos;
+            while pos < data.len() && !data[pos].is_ascii_whitespace() {
+                pos += 1;
+            }
+            if start == pos {
+                return Err(invalid("truncated header"));
+            }
+            header.push(std::str::from_utf8(&data[start..pos]).map_err(|_| invalid("header"))?);
+        }
+        let parse = |s: &str| {
+            s.parse::<u32>()
+                .map_err(|_| invalid("invalid header value"))
+        };
+        let (width, height, maxval) = (parse(header[1])?, parse(header[2])?, parse(header[3])?);
+        let (channels, raw) = match header[0] {
+            "P2" => (1, false),
+            "P3" => (3, false),
+            "P5" => (1, true),
+            "P6" => (3, true),
+            _ => return Err(invalid("unsupported format")),
+        };
+        if maxval == 0 || maxval > 65535 {
+            return Err(invalid("invalid maxval"));
+        }
+
+        let count = (width as usize)
+            .checked_mul(height as usize)
+            .and_then(|n| n.checked_mul(channels))
+            .ok_or_else(|| invalid("image too large"))?;
+        let values: Vec<u32> = match raw {
+            // A single whitespace character separates the header from the raster.
+            true => {
+                let raster = data.get(pos + 1..).unwrap_or_default();
+                match maxval < 256 {
+                    true => raster.iter().map(|&b| b.into()).collect(),
+                    false => raster
+                        .chunks_exact(2)
+                        .map(|b| u16::from_be_bytes([b[0], b[1]]).into())
+                        .collect(),
+                }
+            }
+            false => std::str::from_utf8(&data[pos..])
+                .map_err(|_| invalid("raster"))?
+                .split_ascii_whitespace()
+                .map(parse)
+                .collect::<io::Result<_>>()?,
+        };
+        if values.len() < count {
+            return Err(invalid("truncated raster"));
+        }
+
+        let scale = 1. / f64::from(maxval);
+        let mut image = Self::new(width, height);
+        for (pixel, v) in image.pixels.iter_mut().zip(values.chunks_exact(channels)) {
+            *pixel = match *v {
+                [l] => Color3::new(1., 1., 1.) * (f64::from(l) * scale),
+                [r, g, b] => Color3::new(f64::from(r), f64::from(g), f64::from(b)) * scale,
+                _ => unreachable!(),
+            };
+        }
+        Ok(image)
+    }
+
+    fn index(&self, i: u32, j: u32) -> usize {
+        j as usize * self.width as usize + i as usize
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn read_pnm() {
+        // Arrange
+        let plain = b"P3\n# comment\n2 1\n255\n255 0 51 0 255 0\n".to_vec();
+        let mut raw = b"P6 2 1 255\n".to_vec();
+        raw.extend([255, 0, 51, 0, 255, 0]);
+        let plain_16 = b"P3 2 1 65535 65535 0 13107 0 65535 0".to_vec();
+        let mut raw_16 = b"P6 2 1 65535\n".to_vec();
+        for v in [65535_u16, 0, 13107, 0, 65535, 0] {
+            raw_16.extend(v.to_be_bytes());
+        }
+
+        // Act
+        let images = [plain, raw, plain_16, raw_16].map(|data| Image::read_pnm(&data).unwrap());
+
+        // Assert
+        for image in images {
+            assert_eq!((image.width(), image.height()), (2, 1));
+            assert_eq!(image.get(0, 0), Color3::new(1., 0., 0.2));
+            assert_eq!(image.get(1, 0), Color3::new(0., 1., 0.));
+        }
+    }
+
+    #[test]
+    fn read_pnm_invalid() {
+        // Arrange
+        let mut raw = b"P6 2 1 255\n".to_vec();
+        raw.extend([255, 0, 51, 0, 255]);
+        let mut raw_16 = b"P6 1 1 65535\n".to_vec();
+        raw_16.extend([255, 255, 0, 0, 0]);
+        let inputs = [
+            b"P3 2 1 255 255 0 51 0 255".to_vec(),
+            raw,
+            raw_16,
+            b"P3 2 1".to_vec(),
+            // The pixel count overflows a `u32`, and with the channels a 64-bit `usize`.
+            b"P3 4294967295 4294967295 255 0 0 0".to_vec(),
+        ];
+
+        // Act
+        let errors = inputs.map(|data| Image::read_pnm(&data).unwrap_err());
+
+        // Assert
+        for error in errors {
+            assert_eq!(error.kind(), io::ErrorKind::InvalidData);
+        }
+    }
+}
@@ -0,0 +1,98 @@
+use crate::{
+    hittable::Hittable,
+    integrator::{Integrator, RAY_T},
+    interval::Interval,
+    ray::Ray,
+    sampler::PixelSample,
+    sampling::{cosine_hemisphere, Onb},
+    scene::Scene,
+    vec3::Color3,
+};
+
+/// Grayscale visibility of the first surface along the ray: the fraction of cosine-weighted
+/// directions in which nothing lies within `max_distance`, ignoring materials and lights.
+///
+/// Meant for checking geometry quickly; select it with the camera's `integrator` setting.
+#[derive(Clone, Copy, Debug)]
+pub struct AmbientOcclusion {
+    /// Distance beyond which hits don't occlude
+    pub max_distance: f64,
+    /// Count of occlusion rays per camera ray
+    pub rays: u32,
+}
+
+impl Default for AmbientOcclusion {
+    fn default() -> Self {
+        Self {
+            max_distance: f64::INFINITY,
+            rays: 16,
+        }
+    }
+}
+
+impl AmbientOcclusion {
+    pub fn new(rays: u32, max_distance: f64) -> Self {
+        Self {
+            max_distance,
+            rays: rays.max(1),
+        }
+    }
+}
+
+impl Integrator for AmbientOcclusion {
+    fn li(&self, r: &Ray, scene: &Scene, sample: &mut PixelSample) -> Color3 {
+        let Some(rec) = scene.world.hit(r, &RAY_T) else {
+            return Color3::new(1., 1., 1.);
+        };
+
+        let uvw = Onb::new(&rec.normal);
+        let ray_t = Interval::new(RAY_T.min, self.max_distance);
+        let unoccluded = (0..self.rays)
+            .filter(|_| {
+                // Unit directions, so the distance is the ray parameter.
+                let direction = uvw.local(&cosine_hemisphere(sample.get_2d()));
+                !scene.world.occluded(&Ray::new(rec.p, direction), &ray_t)
+            })
+            .count();
+        let visibility = unoccluded as f64 / f64::from(self.rays);
+        Color3::new(visibility, visibility, visibility)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::rc::Rc;
+
+    use super::*;
+    use crate::{
+        hittable::Sphere,
+        material::{Lambertian, Material},
+        sampler::IndependentSampler,
+        vec3::{Point3, Vec3},
+    };
+
+    #[test]
+    fn max_distance() {
+        // Arrange
+        let mut scene = Scene::new();
+        let material: Rc<dyn Material> = Rc::new(Lambertian::new(Color3::new(0.5, 0.5, 0.5)));
+        scene.add(Sphere::new(
+            Point3::new(0., -1000., 0.),
+            1000.,
+            Rc::clone(&material),
+        ));
+        scene.add(Sphere::new(Point3::new(0., 1.5, 0.), 1., material));
+        let r = Ray::new(Point3::new(0.1, 0.2, 5.), Vec3::new(0., -0.2, -5.));
+        let sampler = IndependentSampler::new(1);
+        let mut sample = PixelSample::new(&sampler, (0, 0), 0);
+
+        // Act
+        let near = AmbientOcclusion::new(64, 0.1).li(&r, &scene, &mut sample);
+        let far = AmbientOcclusion::new(64, f64::INFINITY).li(&r, &scene, &mut sample);
+
+        // Assert
+        assert_eq!(near, Color3::new(1., 1., 1.));
+        assert!(far.x < 0.9);
+        assert_eq!(far.x, far.y);
+    }
+}
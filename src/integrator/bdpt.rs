@@ -0,0 +1,396 @@
+use crate::{
+    hittable::{HitRecord, Hittable},
+    integrator::{Integrator, RAY_T},
+    interval::Interval,
+    ray::Ray,
+    sampler::PixelSample,
+    sampling::{cosine_hemisphere, cosine_hemisphere_pdf, Onb},
+    scene::Scene,
+    vec3::{Color3, Point3, Vec3},
+};
+
+/// Bidirectional path tracer, after Veach's thesis and pbrt.
+///
+/// Every sample traces one subpath from the camera and one from a point on the scene's
+/// lights, and connects each prefix of the one to each prefix of the other. The ways of
+/// building the same path are combined with the power heuristic of multiple importance
+/// sampling.
+///
+/// Connections of light subpaths straight to the camera are not made, since radiance is
+/// computed per camera ray rather than splatted onto the film. Emissive objects must be
+/// added with [`Scene::add_light`] to be sampled from the lights side. Objects that don't
+/// cast shadows still block connections, since light subpaths can't pass through them.
+#[derive(Clone, Copy, Debug)]
+pub struct BdptIntegrator {
+    /// Most surfaces a path visits, counting the one on the light
+    pub max_depth: u32,
+}
+
+impl BdptIntegrator {
+    pub fn new(max_depth: u32) -> Self {
+        Self { max_depth }
+    }
+}
+
+enum VertexKind {
+    Camera,
+    /// Point sampled on the light with the given ID, emitting `Le` towards its normal
+    Light(u32),
+    /// Surface hit by the ray arriving at it
+    Surface(Ray, HitRecord),
+}
+
+/// A vertex of a camera or light subpath.
+struct Vertex {
+    /// Product of the weights of the path up to the vertex
+    beta: Color3,
+    /// Whether the vertex scatters specularly, so can't be connected to
+    delta: bool,
+    kind: VertexKind,
+    normal: Vec3,
+    p: Point3,
+    /// Density over area of sampling the vertex from the previous one of its subpath
+    pdf_fwd: f64,
+    /// Density over area of sampling the vertex from the next one, if the path were traced
+    /// the other way
+    pdf_rev: f64,
+}
+
+impl Vertex {
+    fn new(kind: VertexKind, p: Point3, normal: Vec3, beta: Color3, pdf_fwd: f64) -> Self {
+        Self {
+            beta,
+            delta: false,
+            kind,
+            normal,
+            p,
+            pdf_fwd,
+            pdf_rev: 0.,
+        }
+    }
+
+    /// Returns the BSDF times the cosine for light going between this vertex and `other`.
+    fn f(&self, other: &Vertex) -> Color3 {
+        match &self.kind {
+            VertexKind::Surface(r_in, rec) => rec.material.eval(r_in, rec, &(other.p - self.p)),
+            _ => Color3::new(0., 0., 0.),
+        }
+    }
+
+    /// Converts a density over solid angle around this vertex into one over area at `next`.
+    fn convert_density(&self, pdf: f64, next: &Vertex) -> f64 {
+        let w = next.p - self.p;
+        let distance_squared = w.length_squared();
+        if distance_squared == 0. {
+            return 0.;
+        }
+        let cos_theta = next.normal.dot(&w).abs() / distance_squared.sqrt();
+        pdf * cos_theta / distance_squared
+    }
+
+    /// Returns the density over area at `next` of continuing from this vertex to it, for a
+    /// path arriving from `prev`.
+    fn pdf(&self, prev: Option<&Vertex>, next: &Vertex) -> f64 {
+        let direction = next.p - self.p;
+        let pdf = match &self.kind {
+            VertexKind::Surface(r_in, rec) => {
+                let r_in = match prev {
+                    Some(prev) => Ray::new(prev.p, self.p - prev.p),
+                    None => r_in.clone(),
+                };
+                rec.material.pdf(&r_in, rec, &direction)
+            }
+            _ => self.pdf_emission(&direction),
+        };
+        self.convert_density(pdf, next)
+    }
+
+    /// Returns the density over solid angle of light leaving the vertex in `direction`, as
+    /// sampled on lights.
+    fn pdf_emission(&self, direction: &Vec3) -> f64 {
+        cosine_hemisphere_pdf(self.normal.dot(&direction.unit_vector()))
+    }
+
+    /// Returns the light emitted at this vertex onto the object with ID `receiver`.
+    fn emitted(&self, scene: &Scene, receiver: Option<u32>) -> Color3 {
+        match &self.kind {
+            VertexKind::Surface(_, rec) => scene.emitted(rec, receiver),
+            _ => Color3::new(0., 0., 0.),
+        }
+    }
+
+    fn object_id(&self) -> Option<u32> {
+        match &self.kind {
+            VertexKind::Surface(_, rec) => Some(rec.object_id),
+            _ => None,
+        }
+    }
+}
+
+impl Integrator for BdptIntegrator {
+    fn li(&self, r: &Ray, scene: &Scene, sample: &mut PixelSample) -> Color3 {
+        let max_depth = self.max_depth as usize;
+        let white = Color3::new(1., 1., 1.);
+        let mut camera_path = vec![Vertex::new(
+            VertexKind::Camera,
+            r.origin,
+            r.direction,
+            white,
+            0.,
+        )];
+        // Paths escaping into the background can't be found from the lights.
+        let mut radiance = random_walk(scene, r.clone(), white, 0., max_depth, &mut camera_path);
+
+        let mut light_path = Vec::new();
+        if let Some((rec, pdf_area)) = scene.lights.sample_surface(sample.get_2d()) {
+            let le = rec.material.emitted(&rec);
+            let y0 = Vertex::new(
+                VertexKind::Light(rec.object_id),
+                rec.p,
+                rec.normal,
+                le / pdf_area,
+                pdf_area,
+            );
+            let direction = Onb::new(&rec.normal).local(&cosine_hemisphere(sample.get_2d()));
+            let pdf_dir = y0.pdf_emission(&direction);
+            let beta = y0.beta * rec.normal.dot(&direction.unit_vector()) / pdf_dir;
+            light_path.push(y0);
+            if pdf_dir > 0. {
+                let ray = Ray::new(rec.p, direction);
+                random_walk(
+                    scene,
+                    ray,
+                    beta,
+                    pdf_dir,
+                    max_depth.saturating_sub(1),
+                    &mut light_path,
+                );
+            }
+            // Light linking cuts off the light subpath at its first surface.
+            let linked = light_path
+                .get(1)
+                .and_then(Vertex::object_id)
+                .is_none_or(|id| scene.is_linked(rec.object_id, id));
+            if !linked {
+                light_path.truncate(1);
+            }
+        }
+
+        for t in 2..=camera_path.len() {
+            for s in 0..=light_path.len().min(max_depth + 1 - t) {
+                let contribution = connect(scene, &light_path, &camera_path, s, t);
+                if contribution != Color3::new(0., 0., 0.) {
+                    radiance += mis_weight(scene, &light_path, &camera_path, s, t) * contribution;
+                }
+            }
+        }
+        radiance
+    }
+}
+
+/// Extends `path` by tracing `ray` through up to `max_vertices` surfaces, returning the
+/// light from the background if the path leaves the scene.
+///
+/// `pdf_dir` is the density over solid angle of `ray` having been sampled from the last
+/// vertex of `path`.
+fn random_walk(
+    scene: &Scene,
+    mut ray: Ray,
+    mut beta: Color3,
+    mut pdf_dir: f64,
+    max_vertices: usize,
+    path: &mut Vec<Vertex>,
+) -> Color3 {
+    for _ in 0..max_vertices {
+        let Some(rec) = scene.world.hit(&ray, &RAY_T) else {
+            return beta * scene.background(&ray);
+        };
+        let (p, normal) = (rec.p, rec.normal);
+        let mut vertex = Vertex::new(VertexKind::Surface(ray, rec), p, normal, beta, 0.);
+        let prev = path.last().expect("paths start on the camera or a light");
+        vertex.pdf_fwd = prev.convert_density(pdf_dir, &vertex);
+        path.push(vertex);
+
+        let n = path.len();
+        let vertex = &path[n - 1];
+        let VertexKind::Surface(r_in, rec) = &vertex.kind else {
+            unreachable!()
+        };
+        let Some((attenuation, scattered)) = rec.material.scatter(r_in, rec) else {
+            break;
+        };
+
+        let delta = rec.material.is_specular();
+        let pdf_rev = match delta {
+            true => {
+                pdf_dir = 0.;
+                0.
+            }
+            false => {
+                pdf_dir = rec.material.pdf(r_in, rec, &scattered.direction);
+                let reversed = Ray::new(scattered.origin, -scattered.direction);
+                rec.material.pdf(&reversed, rec, &-r_in.direction)
+            }
+        };
+        let pdf_rev = vertex.convert_density(pdf_rev, &path[n - 2]);
+        path[n - 2].pdf_rev = pdf_rev;
+        path[n - 1].delta = delta;
+        beta = beta * attenuation;
+        ray = scattered;
+    }
+    Color3::new(0., 0., 0.)
+}
+
+/// Returns the unweighted contribution of the path made of the first `s` vertices of
+/// `light_path` and the first `t` of `camera_path`.
+fn connect(
+    scene: &Scene,
+    light_path: &[Vertex],
+    camera_path: &[Vertex],
+    s: usize,
+    t: usize,
+) -> Color3 {
+    let black = Color3::new(0., 0., 0.);
+    let pt = &camera_path[t - 1];
+    if s == 0 {
+        return pt.beta * pt.emitted(scene, camera_path[t - 2].object_id());
+    }
+
+    let qs = &light_path[s - 1];
+    if pt.delta || qs.delta {
+        return black;
+    }
+    let direction = qs.p - pt.p;
+    let distance_squared = direction.length_squared();
+    let f_camera = pt.f(qs);
+    let f_light = match qs.kind {
+        VertexKind::Light(light) => {
+            if pt.object_id().is_some_and(|id| !scene.is_linked(light, id)) {
+                return black;
+            }
+            let cos_theta = qs.normal.dot(&-direction) / distance_squared.sqrt();
+            Color3::new(1., 1., 1.) * cos_theta.max(0.)
+        }
+        _ => qs.f(pt),
+    };
+    let contribution = pt.beta * f_camera * f_light * qs.beta / distance_squared;
+    if contribution == black || !unoccluded(scene, pt.p, direction) {
+        return black;
+    }
+    contribution
+}
+
+/// Whether nothing lies between `origin` and `origin + direction`, counting objects that
+/// don't cast shadows.
+fn unoccluded(scene: &Scene, origin: Point3, direction: Vec3) -> bool {
+    let epsilon = RAY_T.min / direction.length();
+    let ray_t = Interval::new(epsilon, 1. - epsilon);
+    scene
+        .world
+        .hit(&Ray::new(origin, direction), &ray_t)
+        .is_none()
+}
+
+/// Returns the power heuristic weight of connecting `s` light vertices and `t` camera
+/// vertices, against the other ways of sampling the same path.
+///
+/// The sum over strategies is built incrementally from the ratios of the reverse and forward
+/// densities of each vertex, as in pbrt.
+fn mis_weight(
+    scene: &Scene,
+    light_path: &[Vertex],
+    camera_path: &[Vertex],
+    s: usize,
+    t: usize,
+) -> f64 {
+    let pt = &camera_path[t - 1];
+    let qs = s.checked_sub(1).map(|i| &light_path[i]);
+    let remap0 = |pdf: f64| match pdf != 0. {
+        true => pdf,
+        false => 1.,
+    };
+
+    // Densities and deltas of both subpaths, as changed by the connection.
+    let mut camera = camera_path[..t]
+        .iter()
+        .map(|v| (v.pdf_fwd, v.pdf_rev, v.delta))
+        .collect::<Vec<_>>();
+    let mut light = light_path[..s]
+        .iter()
+        .map(|v| (v.pdf_fwd, v.pdf_rev, v.delta))
+        .collect::<Vec<_>>();
+    camera[t - 1].2 = false;
+    camera[t - 1].1 = match qs {
+        Some(qs) => qs.pdf(s.checked_sub(2).map(|i| &light_path[i]), pt),
+        None => {
+            let pdf = scene.lights.surface_pdf(&pt.p);
+            // Emitters outside the lights are only found by camera paths.
+            if pdf == 0. {
+                return 1.;
+            }
+            pdf
+        }
+    };
+    // The camera vertex only matters to strategies that end on it, which aren't used.
+    if t > 2 {
+        let pt_minus = &camera_path[t - 2];
+        camera[t - 2].1 = match qs {
+            Some(qs) => pt.pdf(Some(qs), pt_minus),
+            None => pt.convert_density(pt.pdf_emission(&(pt_minus.p - pt.p)), pt_minus),
+        };
+    }
+    if let Some(qs) = qs {
+        light[s - 1].2 = false;
+        light[s - 1].1 = pt.pdf(Some(&camera_path[t - 2]), qs);
+        if s > 1 {
+            light[s - 2].1 = qs.pdf(Some(pt), &light_path[s - 2]);
+        }
+    }
+
+    let mut sum = 0.;
+    let mut ratio = 1.;
+    for i in (2..t).rev() {
+        ratio *= remap0(camera[i].1) / remap0(camera[i].0);
+        if !camera[i].2 && !camera[i - 1].2 {
+            sum += ratio * ratio;
+        }
+    }
+    ratio = 1.;
+    for i in (0..s).rev() {
+        ratio *= remap0(light[i].1) / remap0(light[i].0);
+        let delta_before = i > 0 && light[i - 1].2;
+        if !light[i].2 && !delta_before {
+            sum += ratio * ratio;
+        }
+    }
+    1. / (1. + sum)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{
+        hittable::LightLink,
+        integrator::{
+            tests::{caustic_scene, mean},
+            NeePathIntegrator,
+        },
+        util::seed_random,
+    };
+
+    #[test]
+    fn caustics() {
+        // Arrange
+        seed_random(1);
+        let (scene, r) = caustic_scene(true, LightLink::All);
+        let n = 40_000;
+
+        // Act
+        let bdpt = mean(&BdptIntegrator::new(6), &scene, &r, n);
+        let nee = mean(&NeePathIntegrator::new(6), &scene, &r, n);
+
+        // Assert
+        assert!(nee > 0.1);
+        assert!((bdpt - nee).abs() < 0.08 * nee, "{bdpt} vs {nee}");
+    }
+}
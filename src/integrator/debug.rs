@@ -0,0 +1,41 @@
+use crate::{
+    hittable::Hittable,
+    integrator::{Integrator, RAY_T},
+    ray::Ray,
+    sampler::PixelSample,
+    scene::Scene,
+    vec3::Color3,
+};
+
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum DebugMode {
+    /// Normals facing the ray, mapped from [-1,1] to [0,1]
+    #[default]
+    Normals,
+    /// Surface colors of the materials, and the background on a miss
+    Albedo,
+}
+
+/// Shows a property of the first surface along the ray, without any lighting.
+#[derive(Clone, Copy, Debug, Default)]
+pub struct DebugIntegrator {
+    pub mode: DebugMode,
+}
+
+impl DebugIntegrator {
+    pub fn new(mode: DebugMode) -> Self {
+        Self { mode }
+    }
+}
+
+impl Integrator for DebugIntegrator {
+    fn li(&self, r: &Ray, scene: &Scene, _sample: &mut PixelSample) -> Color3 {
+        let rec = scene.world.hit(r, &RAY_T);
+        match (self.mode, rec) {
+            (DebugMode::Normals, Some(rec)) => 0.5 * (rec.normal + Color3::new(1., 1., 1.)),
+            (DebugMode::Normals, None) => Color3::new(0., 0., 0.),
+            (DebugMode::Albedo, Some(rec)) => rec.material.albedo(&rec),
+            (DebugMode::Albedo, None) => scene.background(r),
+        }
+    }
+}
@@ -0,0 +1,78 @@
+use crate::{
+    hittable::{HitRecord, Hittable},
+    integrator::{bsdf_weight, sample_light, Integrator, RAY_T},
+    ray::Ray,
+    sampler::PixelSample,
+    scene::Scene,
+    vec3::Color3,
+};
+
+/// Only the light arriving directly from lights and the background at the first
+/// non-specular surface, seen through any mirrors and glass in front of it.
+#[derive(Clone, Copy, Debug)]
+pub struct DirectLighting {
+    /// Most surfaces a path visits, counting specular ones
+    pub max_depth: u32,
+}
+
+impl DirectLighting {
+    pub fn new(max_depth: u32) -> Self {
+        Self { max_depth }
+    }
+}
+
+impl Integrator for DirectLighting {
+    fn li(&self, r: &Ray, scene: &Scene, _sample: &mut PixelSample) -> Color3 {
+        let mut ray = r.clone();
+        let mut radiance = Color3::new(0., 0., 0.);
+        let mut throughput = Color3::new(1., 1., 1.);
+        // The object the current ray was scattered from
+        let mut receiver = None;
+
+        for bounce in 1..=self.max_depth {
+            let Some(rec) = scene.world.hit(&ray, &RAY_T) else {
+                return radiance + throughput * scene.background(&ray);
+            };
+            radiance += throughput
+                * (scene.emitted(&rec, receiver) + scene.emitted_behind(&ray, &rec, receiver));
+
+            if bounce == self.max_depth {
+                break;
+            }
+            // Combine a sample of the lights with one of the material, which may be lost
+            // below the surface.
+            if !rec.material.is_specular() {
+                radiance += throughput * sample_light(scene, &ray, &rec);
+            }
+            let Some((attenuation, scattered)) = rec.material.scatter(&ray, &rec) else {
+                break;
+            };
+            if rec.material.is_specular() {
+                throughput = throughput * attenuation;
+                receiver = Some(rec.object_id);
+                ray = scattered;
+                continue;
+            }
+
+            radiance += throughput * attenuation * Self::found_light(scene, &ray, &rec, &scattered);
+            break;
+        }
+        radiance
+    }
+}
+
+impl DirectLighting {
+    /// Returns the weighted light arriving along `scattered`, sampled from the material
+    /// at `rec`.
+    fn found_light(scene: &Scene, r_in: &Ray, rec: &HitRecord, scattered: &Ray) -> Color3 {
+        match scene.world.hit(scattered, &RAY_T) {
+            Some(light_rec) => {
+                let receiver = Some(rec.object_id);
+                bsdf_weight(scene, r_in, rec, &scattered.direction)
+                    * (scene.emitted(&light_rec, receiver)
+                        + scene.emitted_behind(scattered, &light_rec, receiver))
+            }
+            None => scene.background(scattered),
+        }
+    }
+}
@@ -0,0 +1,277 @@
+//! Light transport algorithms, computing the radiance arriving along camera rays.
+
+mod ao;
+mod bdpt;
+mod debug;
+mod direct;
+mod nee;
+mod path;
+mod photon;
+
+use std::fmt::Debug;
+
+use crate::{
+    hittable::{HitRecord, Hittable},
+    interval::Interval,
+    ray::Ray,
+    sampler::PixelSample,
+    scene::Scene,
+    util::random_double,
+    vec3::{Color3, Vec3},
+};
+
+pub use self::{
+    ao::AmbientOcclusion,
+    bdpt::BdptIntegrator,
+    debug::{DebugIntegrator, DebugMode},
+    direct::DirectLighting,
+    nee::NeePathIntegrator,
+    path::PathIntegrator,
+    photon::PhotonMapIntegrator,
+};
+
+/// Range of hits along secondary rays, skipping the surface they start on.
+const RAY_T: Interval = Interval::new(0.001, f64::INFINITY);
+
+/// Bounces after which paths are terminated randomly, depending on their throughput.
+const ROULETTE_MIN_BOUNCES: u32 = 3;
+
+/// Highest probability of a path surviving Russian roulette, so paths that lose no energy,
+/// like those between glass spheres, still end.
+const ROULETTE_MAX_SURVIVAL: f64 = 0.95;
+
+pub trait Integrator: Debug {
+    /// Prepare data that depends on the scene, before any radiance is computed.
+    fn preprocess(&self, _scene: &Scene) {}
+
+    /// Returns the radiance arriving at the camera along `r`.
+    fn li(&self, r: &Ray, scene: &Scene, sample: &mut PixelSample) -> Color3;
+}
+
+/// Decides whether a path continues after `bounce` bounces.
+///
+/// Paths survive with a probability proportional to their throughput, which is weighted
+/// up for survivors to keep the estimate unbiased.
+fn survives_roulette(bounce: u32, throughput: &mut Color3) -> bool {
+    if bounce < ROULETTE_MIN_BOUNCES {
+        return true;
+    }
+    let survival = throughput
+        .x
+        .max(throughput.y)
+        .max(throughput.z)
+        .min(ROULETTE_MAX_SURVIVAL);
+    if random_double() >= survival {
+        return false;
+    }
+    *throughput /= survival;
+    true
+}
+
+/// Returns the light from a sampled point on the scene's lights, reflected at `rec` along
+/// `r_in`, weighted against finding it by sampling the material.
+fn sample_light(scene: &Scene, r_in: &Ray, rec: &HitRecord) -> Color3 {
+    let black = Color3::new(0., 0., 0.);
+    if scene.lights.is_empty() {
+        return black;
+    }
+
+    let Some(direction) = scene.lights.random(&rec.p) else {
+        return black;
+    };
+    let light_pdf = scene.lights.pdf_value(&rec.p, &direction);
+    let f = rec.material.eval(r_in, rec, &direction);
+    if light_pdf <= 0. || f == black {
+        return black;
+    }
+
+    let shadow_ray = Ray::new(rec.p, direction);
+    let Some(light_rec) = scene.lights.hit(&shadow_ray, &RAY_T) else {
+        return black;
+    };
+    let unoccluded_t = Interval::new(RAY_T.min, light_rec.t - RAY_T.min);
+    if scene.world.occluded(&shadow_ray, &unoccluded_t) {
+        return black;
+    }
+    let bsdf_pdf = rec.material.pdf(r_in, rec, &direction);
+    power_heuristic(light_pdf, bsdf_pdf) * f * scene.emitted(&light_rec, Some(rec.object_id))
+        / light_pdf
+}
+
+/// Returns the weight of light found by sampling the material at `rec` in `direction`,
+/// against finding it by sampling the lights.
+fn bsdf_weight(scene: &Scene, r_in: &Ray, rec: &HitRecord, direction: &Vec3) -> f64 {
+    if rec.material.is_specular() || scene.lights.is_empty() {
+        return 1.;
+    }
+    let bsdf_pdf = rec.material.pdf(r_in, rec, direction);
+    power_heuristic(bsdf_pdf, scene.lights.pdf_value(&rec.p, direction))
+}
+
+/// Veach's power heuristic with an exponent of two, for one sample of each strategy.
+fn power_heuristic(f_pdf: f64, g_pdf: f64) -> f64 {
+    let (f2, g2) = (f_pdf * f_pdf, g_pdf * g_pdf);
+    match f2 + g2 > 0. {
+        true => f2 / (f2 + g2),
+        false => 0.,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::rc::Rc;
+
+    use super::*;
+    use crate::{
+        hittable::{LightLink, ObjectFlags, Sphere},
+        material::{Dielectric, DiffuseLight, Lambertian, Material, Metal},
+        sampler::IndependentSampler,
+        scene::Background,
+        util::seed_random,
+        vec3::Point3,
+    };
+
+    /// Returns the mean green radiance `integrator` finds along `r` over `n` samples.
+    pub(super) fn mean(integrator: &dyn Integrator, scene: &Scene, r: &Ray, n: u32) -> f64 {
+        let sampler = IndependentSampler::new(1);
+        integrator.preprocess(scene);
+        let sum = (0..n).fold(Color3::default(), |sum, i| {
+            let mut sample = PixelSample::new(&sampler, (0, 0), i);
+            sum + integrator.li(r, scene, &mut sample)
+        });
+        sum.y / f64::from(n)
+    }
+
+    /// Returns a scene with a glass ball between a light and the floor, and a camera ray to
+    /// the floor below the ball, which only caustics light. With `sampled_light` false, the
+    /// light is left out of the scene's lights. The floor and ball have IDs 1 and 2.
+    pub(super) fn caustic_scene(
+        sampled_light: bool,
+        light_link: LightLink,
+    ) -> (Scene<'static>, Ray) {
+        let mut scene = Scene::new().with_background(Background::Color(Color3::new(0., 0., 0.)));
+        let ground = Rc::new(Lambertian::new(Color3::new(0.5, 0.5, 0.5)));
+        let light = Rc::new(DiffuseLight::new(Color3::new(10., 10., 10.)));
+        scene.add(Sphere::new(Point3::new(0., -1000., 0.), 1000., ground));
+        scene.add(Sphere::new(
+            Point3::new(0., 3., 0.),
+            1.,
+            Rc::new(Dielectric::new(1.5)),
+        ));
+        let light = Sphere::new(Point3::new(0., 7., 0.), 1., light);
+        let flags = ObjectFlags {
+            light_link,
+            ..Default::default()
+        };
+        match sampled_light {
+            true => scene.add_light_with_flags(light, flags),
+            false => scene.add_with_flags(light, flags),
+        };
+        let r = Ray::new(Point3::new(4., 1., 0.), Vec3::new(-4., -1., 0.));
+        (scene, r)
+    }
+
+    #[test]
+    fn roulette() {
+        // Arrange
+        seed_random(1);
+        let throughput = Color3::new(0.3, 0.2, 0.1);
+        let n = 100_000;
+
+        // Act
+        let mut survivors = 0;
+        let mut sum = Color3::default();
+        for _ in 0..n {
+            let mut t = throughput;
+            if survives_roulette(ROULETTE_MIN_BOUNCES, &mut t) {
+                survivors += 1;
+                assert!((t - throughput / 0.3).near_zero());
+                sum += t;
+            }
+        }
+        let mut early = throughput;
+        let survives_early = survives_roulette(ROULETTE_MIN_BOUNCES - 1, &mut early);
+
+        // Assert
+        let survival = f64::from(survivors) / f64::from(n);
+        assert!((survival - 0.3).abs() < 0.01, "{survival}");
+        let mean = sum / f64::from(n);
+        assert!((mean - throughput).length() < 0.01 * throughput.length());
+        assert!(survives_early);
+        assert_eq!(early, throughput);
+    }
+
+    #[test]
+    fn integrators_agree() {
+        // Arrange
+        // Fuzzy metal scatters part of its rays below the surface, which ends those paths.
+        let grounds: [Rc<dyn Material>; 2] = [
+            Rc::new(Lambertian::new(Color3::new(0.5, 0.5, 0.5))),
+            Rc::new(Metal::new(Color3::new(0.5, 0.5, 0.5), 1.)),
+        ];
+        let light: Rc<dyn Material> = Rc::new(DiffuseLight::new(Color3::new(4., 4., 4.)));
+        let r = Ray::new(Point3::new(0., 2., 4.), Vec3::new(0., -2., -4.));
+        let n = 40_000;
+        seed_random(1);
+
+        for ground in grounds {
+            let mut scene =
+                Scene::new().with_background(Background::Color(Color3::new(0., 0., 0.)));
+            scene.add(Sphere::new(Point3::new(0., -1000., 0.), 1000., ground));
+            scene.add_light(Sphere::new(Point3::new(0., 3., 0.), 1., Rc::clone(&light)));
+
+            // Act
+            let path = mean(&PathIntegrator::new(5), &scene, &r, n);
+            let nee = mean(&NeePathIntegrator::new(5), &scene, &r, n);
+            let direct = mean(&DirectLighting::new(5), &scene, &r, n);
+            let bdpt = mean(&BdptIntegrator::new(5), &scene, &r, n);
+
+            // Assert
+            assert!(path > 0.);
+            assert!((nee - path).abs() < 0.08 * path, "{nee} vs {path}");
+            assert!((direct - path).abs() < 0.08 * path, "{direct} vs {path}");
+            assert!((bdpt - path).abs() < 0.08 * path, "{bdpt} vs {path}");
+        }
+    }
+
+    #[test]
+    fn shadow_flag() {
+        // Arrange
+        let build = |ball: bool| {
+            let mut scene =
+                Scene::new().with_background(Background::Color(Color3::new(0., 0., 0.)));
+            let ground = Rc::new(Lambertian::new(Color3::new(0.5, 0.5, 0.5)));
+            let light = Rc::new(DiffuseLight::new(Color3::new(4., 4., 4.)));
+            scene.add(Sphere::new(Point3::new(0., -1000., 0.), 1000., ground));
+            scene.add_light(Sphere::new(Point3::new(0., 3., 0.), 1., light));
+            if ball {
+                // Black, so it only shadows
+                let material = Rc::new(Lambertian::new(Color3::new(0., 0., 0.)));
+                let flags = ObjectFlags {
+                    shadow: false,
+                    ..Default::default()
+                };
+                scene.add_with_flags(Sphere::new(Point3::new(0., 1.2, 0.), 0.5, material), flags);
+            }
+            scene
+        };
+        let (scene, unshadowed_scene) = (build(true), build(false));
+        let r = Ray::new(Point3::new(0., 2., 4.), Vec3::new(0., -2., -4.));
+        let n = 40_000;
+        seed_random(1);
+
+        // Act
+        let unshadowed = mean(&PathIntegrator::new(5), &unshadowed_scene, &r, n);
+        let path = mean(&PathIntegrator::new(5), &scene, &r, n);
+        let nee = mean(&NeePathIntegrator::new(5), &scene, &r, n);
+        let direct = mean(&DirectLighting::new(5), &scene, &r, n);
+
+        // Assert
+        assert!(
+            (path - unshadowed).abs() < 0.08 * unshadowed,
+            "{path} vs {unshadowed}"
+        );
+        assert!((nee - path).abs() < 0.08 * path, "{nee} vs {path}");
+        assert!((direct - path).abs() < 0.08 * path, "{direct} vs {path}");
+    }
+}
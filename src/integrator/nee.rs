@@ -0,0 +1,64 @@
+use crate::{
+    hittable::{HitRecord, Hittable},
+    integrator::{bsdf_weight, sample_light, survives_roulette, Integrator, RAY_T},
+    ray::Ray,
+    sampler::PixelSample,
+    scene::Scene,
+    vec3::Color3,
+};
+
+/// Path tracer that also samples a point on the lights at every non-specular surface, and
+/// combines both ways of finding lights with multiple importance sampling.
+#[derive(Clone, Copy, Debug)]
+pub struct NeePathIntegrator {
+    /// Most surfaces a path visits
+    pub max_depth: u32,
+}
+
+impl NeePathIntegrator {
+    pub fn new(max_depth: u32) -> Self {
+        Self { max_depth }
+    }
+}
+
+impl Integrator for NeePathIntegrator {
+    fn li(&self, r: &Ray, scene: &Scene, _sample: &mut PixelSample) -> Color3 {
+        let mut ray = r.clone();
+        let mut radiance = Color3::new(0., 0., 0.);
+        let mut throughput = Color3::new(1., 1., 1.);
+        // The ray and hit the current ray was scattered from
+        let mut previous: Option<(Ray, HitRecord)> = None;
+
+        for bounce in 1..=self.max_depth {
+            let Some(rec) = scene.world.hit(&ray, &RAY_T) else {
+                return radiance + throughput * scene.background(&ray);
+            };
+            let weight = match &previous {
+                Some((r_in, from)) => bsdf_weight(scene, r_in, from, &ray.direction),
+                None => 1.,
+            };
+            let receiver = previous.as_ref().map(|(_, from)| from.object_id);
+            radiance += weight
+                * throughput
+                * (scene.emitted(&rec, receiver) + scene.emitted_behind(&ray, &rec, receiver));
+
+            if bounce == self.max_depth {
+                break;
+            }
+            if !rec.material.is_specular() {
+                radiance += throughput * sample_light(scene, &ray, &rec);
+            }
+
+            let Some((attenuation, scattered)) = rec.material.scatter(&ray, &rec) else {
+                break;
+            };
+            throughput = throughput * attenuation;
+            if !survives_roulette(bounce, &mut throughput) {
+                break;
+            }
+            previous = Some((ray, rec));
+            ray = scattered;
+        }
+        radiance
+    }
+}
@@ -0,0 +1,124 @@
+use crate::{
+    hittable::Hittable,
+    integrator::{survives_roulette, Integrator, RAY_T},
+    ray::Ray,
+    sampler::PixelSample,
+    scene::Scene,
+    vec3::Color3,
+};
+
+/// Follows a single path of sampled scattering directions, collecting the light it hits.
+#[derive(Clone, Copy, Debug)]
+pub struct PathIntegrator {
+    /// Most surfaces a path visits
+    pub max_depth: u32,
+}
+
+impl PathIntegrator {
+    pub fn new(max_depth: u32) -> Self {
+        Self { max_depth }
+    }
+}
+
+impl Integrator for PathIntegrator {
+    fn li(&self, r: &Ray, scene: &Scene, _sample: &mut PixelSample) -> Color3 {
+        let mut ray = r.clone();
+        let mut radiance = Color3::new(0., 0., 0.);
+        let mut throughput = Color3::new(1., 1., 1.);
+        // The object the current ray was scattered from
+        let mut receiver = None;
+
+        for bounce in 1..=self.max_depth {
+            let Some(rec) = scene.world.hit(&ray, &RAY_T) else {
+                return radiance + throughput * scene.background(&ray);
+            };
+            radiance += throughput
+                * (scene.emitted(&rec, receiver) + scene.emitted_behind(&ray, &rec, receiver));
+
+            // If we've exceeded the ray bounce limit, no more light is gathered.
+            if bounce == self.max_depth {
+                break;
+            }
+            let Some((attenuation, scattered)) = rec.material.scatter(&ray, &rec) else {
+                break;
+            };
+            throughput = throughput * attenuation;
+            if !survives_roulette(bounce, &mut throughput) {
+                break;
+            }
+            receiver = Some(rec.object_id);
+            ray = scattered;
+        }
+        radiance
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::rc::Rc;
+
+    use super::*;
+    use crate::{
+        hittable::{HitRecord, Sphere},
+        material::{Lambertian, Material},
+        sampler::IndependentSampler,
+        util::seed_random,
+        vec3::{Point3, Vec3},
+    };
+
+    /// Diffuse surface that also glows
+    struct Glowing {
+        diffuse: Lambertian,
+        emit: Color3,
+    }
+
+    impl Material for Glowing {
+        fn scatter(&self, r_in: &Ray, rec: &HitRecord) -> Option<(Color3, Ray)> {
+            self.diffuse.scatter(r_in, rec)
+        }
+
+        fn albedo(&self, rec: &HitRecord) -> Color3 {
+            self.diffuse.albedo(rec)
+        }
+
+        fn emitted(&self, _rec: &HitRecord) -> Color3 {
+            self.emit
+        }
+    }
+
+    #[test]
+    fn roulette_is_unbiased() {
+        // Arrange
+        seed_random(1);
+        let albedo = 0.8;
+        let max_depth = 20;
+        let mut scene = Scene::new();
+        scene.add(Sphere::new(
+            Point3::new(0., 0., 0.),
+            10.,
+            Rc::new(Glowing {
+                diffuse: Lambertian::new(Color3::new(albedo, albedo, albedo)),
+                emit: Color3::new(1., 1., 1.),
+            }),
+        ));
+        let r = Ray::new(Point3::new(0., 0., 0.), Vec3::new(0., 0., -1.));
+        let sampler = IndependentSampler::new(1);
+        let n = 20_000;
+
+        // Act
+        let integrator = PathIntegrator::new(max_depth);
+        let sum = (0..n).fold(0., |sum, i| {
+            let mut sample = PixelSample::new(&sampler, (0, 0), i);
+            sum + integrator.li(&r, &scene, &mut sample).y
+        });
+
+        // Assert
+        // Without roulette, every path gathers the emission of all its surfaces.
+        let expected = (1. - f64::powi(albedo, max_depth as i32)) / (1. - albedo);
+        let mean = sum / f64::from(n);
+        assert!(
+            (mean - expected).abs() < 0.02 * expected,
+            "{mean} vs {expected}"
+        );
+    }
+}
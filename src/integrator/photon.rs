@@ -0,0 +1,357 @@
+use std::{
+    cell::RefCell,
+    f64::consts::PI,
+    fmt::{self, Debug},
+};
+
+use crate::{
+    hittable::{HitRecord, Hittable},
+    integrator::{bsdf_weight, sample_light, survives_roulette, Integrator, RAY_T},
+    ray::Ray,
+    sampler::PixelSample,
+    sampling::{cosine_hemisphere, random_2d, Onb},
+    scene::Scene,
+    vec3::{Color3, Point3, Vec3},
+};
+
+/// Path tracer with next event estimation that takes caustics from a photon map, after
+/// Jensen, "Global Illumination using Photon Maps".
+///
+/// Before rendering, photons are shot from the lights and stored where they land on a
+/// diffuse surface after one or more specular bounces. The light of such paths is then
+/// estimated from the density of nearby photons, instead of by the path tracer, which
+/// rarely finds the light through glass.
+///
+/// Only the scene's lights emit photons, so caustics of emitters added without
+/// [`Scene::add_light`] are left to the path tracer.
+#[derive(Debug)]
+pub struct PhotonMapIntegrator {
+    /// Most surfaces a camera path or photon visits
+    pub max_depth: u32,
+    /// Count of photons emitted from the lights
+    pub photon_count: u32,
+    /// Distance around a hit within which photons are gathered
+    pub radius: f64,
+    map: RefCell<PhotonMap>,
+}
+
+impl PhotonMapIntegrator {
+    pub fn new(max_depth: u32, photon_count: u32, radius: f64) -> Self {
+        Self {
+            max_depth,
+            photon_count,
+            radius,
+            map: RefCell::default(),
+        }
+    }
+
+    /// Returns the caustic light leaving `rec` along the reverse of `r_in`, estimated from
+    /// the photons within the gather radius.
+    fn caustics(&self, r_in: &Ray, rec: &HitRecord) -> Color3 {
+        let mut sum = Color3::new(0., 0., 0.);
+        self.map
+            .borrow()
+            .for_each_within(&rec.p, self.radius, |photon| {
+                // The BSDF without the cosine, which the photon's flux already accounts for.
+                let cos_theta = -photon.direction.dot(&rec.normal);
+                if cos_theta > 0. {
+                    let f = rec.material.eval(r_in, rec, &-photon.direction) / cos_theta;
+                    sum += f * photon.power;
+                }
+            });
+        sum / (PI * self.radius * self.radius)
+    }
+
+    /// Trace a photon leaving the light with ID `light` along `ray`, storing it in
+    /// `photons` if it lands on a diffuse surface after a specular bounce.
+    fn trace_photon(
+        &self,
+        scene: &Scene,
+        light: u32,
+        mut ray: Ray,
+        mut power: Color3,
+        photons: &mut Vec<Photon>,
+    ) {
+        let mut after_specular = false;
+        for _ in 0..self.max_depth {
+            let Some(rec) = scene.world.hit(&ray, &RAY_T) else {
+                return;
+            };
+            // Light linking applies to the first surface the light reaches, as on camera
+            // paths.
+            if !after_specular && !scene.is_linked(light, rec.object_id) {
+                return;
+            }
+            if !rec.material.is_specular() {
+                if after_specular {
+                    photons.push(Photon {
+                        axis: 0,
+                        direction: ray.direction.unit_vector(),
+                        p: rec.p,
+                        power,
+                    });
+                }
+                return;
+            }
+
+            let Some((attenuation, scattered)) = rec.material.scatter(&ray, &rec) else {
+                return;
+            };
+            power = power * attenuation;
+            after_specular = true;
+            ray = scattered;
+        }
+    }
+}
+
+impl Integrator for PhotonMapIntegrator {
+    fn preprocess(&self, scene: &Scene) {
+        let mut photons = Vec::new();
+        for _ in 0..self.photon_count {
+            let Some((rec, pdf_area)) = scene.lights.sample_surface(random_2d()) else {
+                break;
+            };
+            // With cosine-weighted directions, each photon carries `Le * π / pdf_area` of
+            // the total flux.
+            let direction = Onb::new(&rec.normal).local(&cosine_hemisphere(random_2d()));
+            let power = rec.material.emitted(&rec) * PI / (pdf_area * f64::from(self.photon_count));
+            let ray = Ray::new(rec.p, direction);
+            self.trace_photon(scene, rec.object_id, ray, power, &mut photons);
+        }
+        *self.map.borrow_mut() = PhotonMap::new(photons);
+    }
+
+    fn li(&self, r: &Ray, scene: &Scene, _sample: &mut PixelSample) -> Color3 {
+        let mut ray = r.clone();
+        let mut radiance = Color3::new(0., 0., 0.);
+        let mut throughput = Color3::new(1., 1., 1.);
+        let mut previous: Option<(Ray, HitRecord)> = None;
+        // Whether the path has bounced specularly since its last diffuse surface, so any
+        // light it finds is a caustic already in the photon map
+        let mut caustic = false;
+
+        for bounce in 1..=self.max_depth {
+            let Some(rec) = scene.world.hit(&ray, &RAY_T) else {
+                return radiance + throughput * scene.background(&ray);
+            };
+            // Photons only carry the caustics of the scene's lights.
+            if !caustic || scene.lights.surface_pdf(&rec.p) <= 0. {
+                let weight = match &previous {
+                    Some((r_in, from)) => bsdf_weight(scene, r_in, from, &ray.direction),
+                    None => 1.,
+                };
+                let receiver = previous.as_ref().map(|(_, from)| from.object_id);
+                radiance += weight
+                    * throughput
+                    * (scene.emitted(&rec, receiver) + scene.emitted_behind(&ray, &rec, receiver));
+            }
+
+            if bounce == self.max_depth {
+                break;
+            }
+            let specular = rec.material.is_specular();
+            if !specular {
+                radiance +=
+                    throughput * (sample_light(scene, &ray, &rec) + self.caustics(&ray, &rec));
+            }
+
+            let Some((attenuation, scattered)) = rec.material.scatter(&ray, &rec) else {
+                break;
+            };
+            caustic = match specular {
+                true => {
+                    caustic
+                        || previous
+                            .as_ref()
+                            .is_some_and(|(_, from)| !from.material.is_specular())
+                }
+                false => false,
+            };
+            throughput = throughput * attenuation;
+            if !survives_roulette(bounce, &mut throughput) {
+                break;
+            }
+            previous = Some((ray, rec));
+            ray = scattered;
+        }
+        radiance
+    }
+}
+
+/// Light arriving at a point, carried by a photon.
+#[derive(Clone, Copy, Debug)]
+struct Photon {
+    /// Splitting axis of the kd-tree node the photon is at
+    axis: usize,
+    /// Direction the photon travelled in, of unit length
+    direction: Vec3,
+    p: Point3,
+    /// Flux carried by the photon
+    power: Color3,
+}
+
+/// Photons in an implicit kd-tree: each subslice has its node at the middle, with the
+/// photons below it on the splitting axis to the left and those above to the right.
+#[derive(Default)]
+struct PhotonMap {
+    photons: Vec<Photon>,
+}
+
+impl PhotonMap {
+    fn new(mut photons: Vec<Photon>) -> Self {
+        build(&mut photons);
+        Self { photons }
+    }
+
+    /// Call `f` with every photon within `radius` of `p`.
+    fn for_each_within(&self, p: &Point3, radius: f64, mut f: impl FnMut(&Photon)) {
+        query(&self.photons, p, radius * radius, &mut f)
+    }
+}
+
+impl Debug for PhotonMap {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("PhotonMap")
+            .field("photons", &self.photons.len())
+            .finish()
+    }
+}
+
+fn axis(p: &Point3, axis: usize) -> f64 {
+    [p.x, p.y, p.z][axis]
+}
+
+/// Arrange `photons` into a kd-tree, splitting at the median of the widest axis.
+fn build(photons: &mut [Photon]) {
+    if photons.is_empty() {
+        return;
+    }
+    let (min, max) = photons.iter().fold(
+        (
+            Point3::new(f64::INFINITY, f64::INFINITY, f64::INFINITY),
+            Point3::new(f64::NEG_INFINITY, f64::NEG_INFINITY, f64::NEG_INFINITY),
+        ),
+        |(min, max), photon| {
+            let p = photon.p;
+            (
+                Point3::new(min.x.min(p.x), min.y.min(p.y), min.z.min(p.z)),
+                Point3::new(max.x.max(p.x), max.y.max(p.y), max.z.max(p.z)),
+            )
+        },
+    );
+    let extent = max - min;
+    let split = (0..3)
+        .max_by(|&a, &b| axis(&extent, a).total_cmp(&axis(&extent, b)))
+        .unwrap_or(0);
+
+    let mid = photons.len() / 2;
+    photons.select_nth_unstable_by(mid, |a, b| axis(&a.p, split).total_cmp(&axis(&b.p, split)));
+    photons[mid].axis = split;
+    let (left, right) = photons.split_at_mut(mid);
+    build(left);
+    build(&mut right[1..]);
+}
+
+fn query(photons: &[Photon], p: &Point3, radius_squared: f64, f: &mut impl FnMut(&Photon)) {
+    if photons.is_empty() {
+        return;
+    }
+    let mid = photons.len() / 2;
+    let node = &photons[mid];
+    if (node.p - *p).length_squared() <= radius_squared {
+        f(node);
+    }
+
+    let distance = axis(p, node.axis) - axis(&node.p, node.axis);
+    let (near, far) = match distance < 0. {
+        true => (&photons[..mid], &photons[mid + 1..]),
+        false => (&photons[mid + 1..], &photons[..mid]),
+    };
+    query(near, p, radius_squared, f);
+    if distance * distance <= radius_squared {
+        query(far, p, radius_squared, f);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{
+        hittable::LightLink,
+        integrator::{
+            tests::{caustic_scene, mean},
+            NeePathIntegrator,
+        },
+        util::{random_double, seed_random},
+    };
+
+    #[test]
+    fn for_each_within() {
+        // Arrange
+        seed_random(1);
+        let photons = (0..1000)
+            .map(|_| Photon {
+                axis: 0,
+                direction: Vec3::new(0., -1., 0.),
+                p: Point3::new(random_double(), random_double(), random_double()),
+                power: Color3::new(1., 1., 1.),
+            })
+            .collect::<Vec<_>>();
+        let p = Point3::new(0.5, 0.5, 0.5);
+        let radius = 0.2;
+        let expected = photons
+            .iter()
+            .filter(|photon| (photon.p - p).length() <= radius)
+            .count();
+        let map = PhotonMap::new(photons);
+
+        // Act
+        let mut found = 0;
+        map.for_each_within(&p, radius, |_| found += 1);
+
+        // Assert
+        assert!(expected > 0);
+        assert_eq!(found, expected);
+    }
+
+    #[test]
+    fn caustics() {
+        // Arrange
+        seed_random(1);
+        let n = 40_000;
+
+        for sampled_light in [true, false] {
+            let (scene, r) = caustic_scene(sampled_light, LightLink::All);
+
+            // Act
+            let photons = mean(&PhotonMapIntegrator::new(6, 200_000, 0.25), &scene, &r, n);
+            let nee = mean(&NeePathIntegrator::new(6), &scene, &r, n);
+
+            // Assert
+            assert!(nee > 0.1);
+            assert!((photons - nee).abs() < 0.1 * nee, "{photons} vs {nee}");
+        }
+    }
+
+    #[test]
+    fn caustics_light_link() {
+        // Arrange
+        seed_random(1);
+        // Unlinking the light from the ball it shines through leaves no caustic, as on
+        // camera paths that find the light through the ball.
+        let links = [LightLink::All, LightLink::Exclude(vec![2])];
+
+        // Act
+        let counts = links.map(|light_link| {
+            let (scene, _) = caustic_scene(true, light_link);
+            let integrator = PhotonMapIntegrator::new(6, 1_000, 0.25);
+            integrator.preprocess(&scene);
+            let count = integrator.map.borrow().photons.len();
+            count
+        });
+
+        // Assert
+        assert!(counts[0] > 0);
+        assert_eq!(counts[1], 0);
+    }
+}
@@ -0,0 +1,69 @@
+//! Renders a scene to stdout as a PPM image.
+//!
+//! Without arguments this renders the final scene with the library's tuned camera;
+//! `--scene NAME` renders one of the reference scenes with its own camera instead.
+
+use std::{env, process::ExitCode};
+
+use hello_raytracing::scenes::ReferenceScene;
+
+const USAGE: &str = "usage: hello-raytracing [--scene NAME] [--samples N] [--list]";
+
+fn main() -> ExitCode {
+    let mut scene = None;
+    let mut samples = None;
+    let mut args = env::args().skip(1);
+    while let Some(arg) = args.next() {
+        match arg.as_str() {
+            "--scene" => {
+                let Some(name) = args.next() else {
+                    return fail("missing scene name");
+                };
+                let Some(reference) = ReferenceScene::from_name(&name) else {
+                    return fail(&format!("unknown scene {name:?}, see --list"));
+                };
+                scene = Some(reference);
+            }
+            "--samples" => match args.next().and_then(|n| n.parse::<u32>().ok()) {
+                Some(n) if n > 0 => samples = Some(n),
+                _ => return fail("--samples needs a positive count"),
+            },
+            "--list" => {
+                for reference in ReferenceScene::ALL {
+                    println!("{}", reference.name());
+                }
+                return ExitCode::SUCCESS;
+            }
+            "-h" | "--help" => {
+                println!("{USAGE}");
+                return ExitCode::SUCCESS;
+            }
+            _ => return fail(&format!("unexpected argument {arg:?}")),
+        }
+    }
+
+    let Some(scene) = scene else {
+        if samples.is_some() {
+            return fail("--samples needs --scene");
+        }
+        hello_raytracing::main();
+        return ExitCode::SUCCESS;
+    };
+    let (world, mut camera) = scene.build();
+    if let Some(samples) = samples {
+        camera = camera.samples_per_pixel(samples);
+    }
+    let result = camera
+        .build()
+        .map_err(|e| e.to_string())
+        .and_then(|camera| camera.render(&world).map_err(|e| e.to_string()));
+    match result {
+        Ok(()) => ExitCode::SUCCESS,
+        Err(e) => fail(&e),
+    }
+}
+
+fn fail(message: &str) -> ExitCode {
+    eprintln!("error: {message}\n{USAGE}");
+    ExitCode::FAILURE
+}
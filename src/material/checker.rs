@@ -0,0 +1,84 @@
+use crate::{
+    hittable::HitRecord,
+    material::Material,
+    ray::Ray,
+    sampling::{cosine_hemisphere, cosine_hemisphere_pdf, random_2d, Onb},
+    vec3::{Color3, Point3, Vec3},
+};
+
+/// Diffuse material alternating between two colors in a checkerboard of cubes, which works
+/// on any shape without texture coordinates.
+pub struct Checker {
+    even: Color3,
+    inv_scale: f64,
+    odd: Color3,
+}
+
+impl Checker {
+    /// Create a checkerboard of cubes with sides of length `scale`.
+    pub fn new(scale: f64, even: Color3, odd: Color3) -> Self {
+        Self {
+            even,
+            inv_scale: 1. / scale,
+            odd,
+        }
+    }
+
+    fn color(&self, p: &Point3) -> Color3 {
+        let cell = |x: f64| (self.inv_scale * x).floor() as i64;
+        match (cell(p.x) + cell(p.y) + cell(p.z)) % 2 == 0 {
+            true => self.even,
+            false => self.odd,
+        }
+    }
+}
+
+impl Material for Checker {
+    fn scatter(&self, _r_in: &Ray, rec: &HitRecord) -> Option<(Color3, Ray)> {
+        let direction = Onb::new(&rec.normal).local(&cosine_hemisphere(random_2d()));
+        Some((self.color(&rec.p), Ray::new(rec.p, direction)))
+    }
+
+    fn albedo(&self, rec: &HitRecord) -> Color3 {
+        self.color(&rec.p)
+    }
+
+    fn eval(&self, r_in: &Ray, rec: &HitRecord, direction: &Vec3) -> Color3 {
+        self.pdf(r_in, rec, direction) * self.color(&rec.p)
+    }
+
+    fn pdf(&self, _r_in: &Ray, rec: &HitRecord, direction: &Vec3) -> f64 {
+        cosine_hemisphere_pdf(rec.normal.dot(&direction.unit_vector()))
+    }
+
+    fn is_specular(&self) -> bool {
+        false
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn color() {
+        // Arrange
+        let (even, odd) = (Color3::new(0., 0., 0.), Color3::new(1., 1., 1.));
+        let checker = Checker::new(0.5, even, odd);
+        let points = [
+            Point3::new(0.1, 0.1, 0.1),
+            Point3::new(0.6, 0.1, 0.1),
+            Point3::new(0.6, 0.6, 0.1),
+            Point3::new(0.6, 0.6, 0.6),
+            Point3::new(-0.1, 0.1, 0.1),
+            Point3::new(-0.1, -0.1, 0.1),
+            Point3::new(-0.6, 0.1, 0.1),
+        ];
+
+        // Act
+        let colors = points.map(|p| checker.color(&p));
+
+        // Assert
+        assert_eq!(colors, [even, odd, even, odd, odd, even, even]);
+    }
+}
@@ -1,4 +1,10 @@
-use crate::{hittable::HitRecord, material::Material, ray::Ray, util::random_double, vec3::Color3};
+use crate::{
+    hittable::HitRecord,
+    material::Material,
+    ray::{Ray, RayKind},
+    util::random_double,
+    vec3::Color3,
+};
 
 pub struct Dielectric {
     index_of_refraction: f64,
@@ -26,14 +32,21 @@ impl Material for Dielectric {
 
         let cannot_refract = refraction_ratio * sin_theta > 1.;
         let schlick = reflectance(cos_theta, refraction_ratio) > random_double();
-        let direction = match cannot_refract || schlick {
-            true => unit_direction.reflect(&rec.normal),
-            false => unit_direction.refract(&rec.normal, refraction_ratio),
+        let (direction, kind) = match cannot_refract || schlick {
+            true => (unit_direction.reflect(&rec.normal), RayKind::Reflection),
+            false => (
+                unit_direction.refract(&rec.normal, refraction_ratio),
+                RayKind::Refraction,
+            ),
         };
 
-        let scattered = Ray::new(rec.p, direction);
+        let scattered = Ray::new(rec.p, direction).with_kind(kind);
         Some((attenuation, scattered))
     }
+
+    fn albedo(&self, _rec: &HitRecord) -> Color3 {
+        Color3::new(1.0, 1.0, 1.0)
+    }
 }
 
 /// Schlick's approximation for reflectance
@@ -42,3 +55,83 @@ fn reflectance(cosine: f64, ref_idx: f64) -> f64 {
     let r0 = r0 * r0;
     r0 + (1. - r0) * (1. - cosine).powi(5)
 }
+
+#[cfg(test)]
+mod tests {
+    use std::rc::Rc;
+
+    use super::*;
+    use crate::{
+        util::seed_random,
+        vec3::{Point3, Vec3},
+    };
+
+    /// Returns a ray travelling along the unit direction `direction` and its hit at the
+    /// origin, on a glass surface whose outside faces +z.
+    fn hit(direction: Vec3) -> (Ray, HitRecord) {
+        let r = Ray::new(Point3::new(0., 0., 0.) - direction, direction);
+        let mut rec = HitRecord::new(Rc::new(Dielectric::new(1.5)), Point3::new(0., 0., 0.), 1.);
+        rec.set_face_normal(&r, &Vec3::new(0., 0., 1.));
+        (r, rec)
+    }
+
+    #[test]
+    fn reflectance_limits() {
+        // Arrange
+        let cosines = (0..=10).map(|i| f64::from(i) / 10.).collect::<Vec<_>>();
+
+        // Act
+        let entering = cosines.iter().map(|&c| reflectance(c, 1. / 1.5));
+        let values = cosines
+            .iter()
+            .map(|&c| reflectance(c, 1.5))
+            .collect::<Vec<_>>();
+
+        // Assert
+        assert!((reflectance(1., 1.5) - 0.04).abs() < 1e-12);
+        assert_eq!(reflectance(0., 1.5), 1.);
+        assert_eq!(reflectance(1., 1.), 0.);
+        assert!(values.windows(2).all(|w| w[0] > w[1]));
+        for (a, b) in entering.zip(&values) {
+            assert!((a - b).abs() < 1e-12);
+        }
+    }
+
+    #[test]
+    fn total_internal_reflection() {
+        // Arrange
+        seed_random(1);
+        // Leaving the glass at 60°, beyond the critical angle of about 41.8°
+        let (r, rec) = hit(Vec3::new(3f64.sqrt() / 2., 0., 0.5));
+
+        for _ in 0..1000 {
+            // Act
+            let (attenuation, scattered) = rec.material.scatter(&r, &rec).unwrap();
+
+            // Assert
+            assert!(!rec.front_face);
+            assert_eq!(attenuation, Color3::new(1., 1., 1.));
+            assert_eq!(scattered.kind, RayKind::Reflection);
+            assert!((scattered.direction - r.direction.reflect(&rec.normal)).near_zero());
+        }
+    }
+
+    #[test]
+    fn reflects_at_schlick_rate() {
+        // Arrange
+        seed_random(2);
+        let samples = 100_000;
+        // Entering the glass at 60°
+        let (r, rec) = hit(Vec3::new(3f64.sqrt() / 2., 0., -0.5));
+
+        // Act
+        let reflected = (0..samples)
+            .filter(|_| rec.material.scatter(&r, &rec).unwrap().1.kind == RayKind::Reflection)
+            .count();
+
+        // Assert
+        let fraction = reflected as f64 / f64::from(samples);
+        let expected = reflectance(0.5, 1. / 1.5);
+        assert!((fraction - expected).abs() < 0.005, "{fraction} {expected}");
+    }
+}
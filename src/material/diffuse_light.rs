@@ -0,0 +1,30 @@
+use crate::{hittable::HitRecord, material::Material, ray::Ray, vec3::Color3};
+
+/// Emits light of the same radiance in all directions from the front face, and scatters
+/// nothing.
+pub struct DiffuseLight {
+    emit: Color3,
+}
+
+impl DiffuseLight {
+    pub fn new(emit: Color3) -> Self {
+        Self { emit }
+    }
+}
+
+impl Material for DiffuseLight {
+    fn scatter(&self, _r_in: &Ray, _rec: &HitRecord) -> Option<(Color3, Ray)> {
+        None
+    }
+
+    fn albedo(&self, _rec: &HitRecord) -> Color3 {
+        self.emit
+    }
+
+    fn emitted(&self, rec: &HitRecord) -> Color3 {
+        match rec.front_face {
+            true => self.emit,
+            false => Color3::new(0., 0., 0.),
+        }
+    }
+}
@@ -2,6 +2,7 @@ use crate::{
     hittable::HitRecord,
     material::Material,
     ray::Ray,
+    sampling::{cosine_hemisphere, cosine_hemisphere_pdf, random_2d, Onb},
     vec3::{Color3, Vec3},
 };
 
@@ -17,15 +18,27 @@ impl Lambertian {
 
 impl Material for Lambertian {
     fn scatter(&self, _r_in: &Ray, rec: &HitRecord) -> Option<(Color3, Ray)> {
-        let mut scatter_direction = rec.normal + Vec3::random_unit_vector();
-
-        // Catch degenerate scatter direction
-        if scatter_direction.near_zero() {
-            scatter_direction = rec.normal;
-        }
+        let uvw = Onb::new(&rec.normal);
+        let scatter_direction = uvw.local(&cosine_hemisphere(random_2d()));
 
         let scattered = Ray::new(rec.p, scatter_direction);
         let attenuation = self.albedo;
         Some((attenuation, scattered))
     }
+
+    fn albedo(&self, _rec: &HitRecord) -> Color3 {
+        self.albedo
+    }
+
+    fn eval(&self, r_in: &Ray, rec: &HitRecord, direction: &Vec3) -> Color3 {
+        self.pdf(r_in, rec, direction) * self.albedo
+    }
+
+    fn pdf(&self, _r_in: &Ray, rec: &HitRecord, direction: &Vec3) -> f64 {
+        cosine_hemisphere_pdf(rec.normal.dot(&direction.unit_vector()))
+    }
+
+    fn is_specular(&self) -> bool {
+        false
+    }
 }
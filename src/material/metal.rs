@@ -1,12 +1,16 @@
+use std::f64::consts::PI;
+
 use crate::{
     hittable::HitRecord,
     material::Material,
-    ray::Ray,
+    ray::{Ray, RayKind},
     vec3::{Color3, Vec3},
 };
 
 pub struct Metal {
     albedo: Color3,
+    /// Radius of the sphere around the tip of the mirror direction that reflections are
+    /// spread over
     fuzz: f64,
 }
 
@@ -20,8 +24,52 @@ impl Material for Metal {
     fn scatter(&self, r_in: &Ray, rec: &HitRecord) -> Option<(Color3, Ray)> {
         let reflected = r_in.direction.unit_vector().reflect(&rec.normal);
 
-        let scattered = Ray::new(rec.p, reflected + self.fuzz * Vec3::random_unit_vector());
+        let scattered = Ray::new(rec.p, reflected + self.fuzz * Vec3::random_unit_vector())
+            .with_kind(RayKind::Reflection);
         let attenuation = self.albedo;
         (scattered.direction.dot(&rec.normal) > 0.).then_some((attenuation, scattered))
     }
+
+    fn albedo(&self, _rec: &HitRecord) -> Color3 {
+        self.albedo
+    }
+
+    fn eval(&self, r_in: &Ray, rec: &HitRecord, direction: &Vec3) -> Color3 {
+        self.pdf(r_in, rec, direction) * self.albedo
+    }
+
+    /// Density of the directions to points on the fuzz sphere, above the surface.
+    fn pdf(&self, r_in: &Ray, rec: &HitRecord, direction: &Vec3) -> f64 {
+        if self.is_specular() {
+            return 0.;
+        }
+        let reflected = r_in.direction.unit_vector().reflect(&rec.normal);
+        let direction = direction.unit_vector();
+        if direction.dot(&rec.normal) <= 0. {
+            return 0.;
+        }
+
+        // The ray `t * direction` meets the sphere of radius `fuzz` around `reflected` where
+        // t² - 2bt + c = 0. Each meeting point adds the uniform area density on the sphere,
+        // converted to solid angle.
+        let b = direction.dot(&reflected);
+        let c = 1. - self.fuzz * self.fuzz;
+        let discriminant = b * b - c;
+        if discriminant < 0. {
+            return 0.;
+        }
+        let sqrt_d = discriminant.sqrt();
+        [b - sqrt_d, b + sqrt_d]
+            .into_iter()
+            .filter(|&t| t > 0.)
+            .map(|t| {
+                let normal = (t * direction - reflected) / self.fuzz;
+                t * t / (4. * PI * self.fuzz * self.fuzz * direction.dot(&normal).abs())
+            })
+            .sum()
+    }
+
+    fn is_specular(&self) -> bool {
+        self.fuzz <= 0.
+    }
 }
@@ -1,11 +1,237 @@
+mod checker;
 mod dielectric;
+mod diffuse_light;
 mod lambertian;
 mod metal;
 
-use crate::{hittable::HitRecord, ray::Ray, vec3::Color3};
+use crate::{
+    hittable::HitRecord,
+    ray::Ray,
+    vec3::{Color3, Vec3},
+};
 
-pub use self::{dielectric::Dielectric, lambertian::Lambertian, metal::Metal};
+pub use self::{
+    checker::Checker, dielectric::Dielectric, diffuse_light::DiffuseLight, lambertian::Lambertian,
+    metal::Metal,
+};
 
 pub trait Material {
+    /// Returns a sampled outgoing ray and the weight of the light it brings back, i.e. the
+    /// BSDF times the cosine over the sampling density.
     fn scatter(&self, r_in: &Ray, rec: &HitRecord) -> Option<(Color3, Ray)>;
+
+    /// Returns the surface color at the hit, independent of lighting.
+    fn albedo(&self, rec: &HitRecord) -> Color3;
+
+    /// Returns the light the surface emits towards the ray that hit it.
+    fn emitted(&self, _rec: &HitRecord) -> Color3 {
+        Color3::new(0., 0., 0.)
+    }
+
+    /// Returns the BSDF times the cosine for light arriving from `direction`.
+    ///
+    /// Zero for specular materials, whose only directions are the sampled ones.
+    fn eval(&self, _r_in: &Ray, _rec: &HitRecord, _direction: &Vec3) -> Color3 {
+        Color3::new(0., 0., 0.)
+    }
+
+    /// Returns the density of [`Material::scatter`] choosing `direction`.
+    fn pdf(&self, _r_in: &Ray, _rec: &HitRecord, _direction: &Vec3) -> f64 {
+        0.
+    }
+
+    /// Whether the material scatters into few enough directions that sampling lights
+    /// would never hit them.
+    fn is_specular(&self) -> bool {
+        true
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::{f64::consts::PI, rc::Rc};
+
+    use super::*;
+    use crate::{
+        sampling::{random_2d, uniform_sphere, uniform_sphere_pdf},
+        util::seed_random,
+        vec3::Point3,
+    };
+
+    const SAMPLES: u32 = 100_000;
+
+    /// Returns a ray and its hit at the origin, on a surface facing +z, arriving from the
+    /// unit direction `from`.
+    fn hit(material: Rc<dyn Material>, from: Vec3) -> (Ray, HitRecord) {
+        let r = Ray::new(Point3::new(0., 0., 0.) + from, -from);
+        let mut rec = HitRecord::new(material, Point3::new(0., 0., 0.), 1.);
+        rec.set_face_normal(&r, &Vec3::new(0., 0., 1.));
+        (r, rec)
+    }
+
+    /// Returns the Monte Carlo estimate of the integral of `f` over the sphere.
+    fn integrate(f: impl Fn(&Vec3) -> f64) -> f64 {
+        let sum = (0..SAMPLES)
+            .map(|_| f(&uniform_sphere(random_2d())))
+            .sum::<f64>();
+        sum / (f64::from(SAMPLES) * uniform_sphere_pdf())
+    }
+
+    fn unit(x: f64, y: f64, z: f64) -> Vec3 {
+        Vec3::new(x, y, z).unit_vector()
+    }
+
+    #[test]
+    fn lambertian_cosine_distribution() {
+        // Arrange
+        seed_random(1);
+        let material: Rc<dyn Material> = Rc::new(Lambertian::new(Color3::new(0.5, 0.5, 0.5)));
+        let (r, rec) = hit(material, unit(0.3, 0.2, 1.));
+        let mut bins = [0; 10];
+
+        // Act
+        for _ in 0..SAMPLES {
+            let (_, scattered) = rec.material.scatter(&r, &rec).unwrap();
+            let cos_theta = scattered.direction.unit_vector().z;
+            assert!(cos_theta >= 0.);
+            // With a cosine density, the squared cosine is uniform.
+            bins[((cos_theta * cos_theta * 10.) as usize).min(9)] += 1;
+        }
+
+        // Assert
+        for count in bins {
+            let fraction = f64::from(count) / f64::from(SAMPLES);
+            assert!((fraction - 0.1).abs() < 0.005, "{bins:?}");
+        }
+    }
+
+    #[test]
+    fn white_furnace() {
+        // Arrange
+        seed_random(2);
+        let material: Rc<dyn Material> = Rc::new(Lambertian::new(Color3::new(1., 1., 1.)));
+        let (r, rec) = hit(material, unit(-0.5, 0.1, 1.));
+
+        // Act
+        let reflected = integrate(|d| rec.material.eval(&r, &rec, d).x);
+        let (attenuation, _) = rec.material.scatter(&r, &rec).unwrap();
+
+        // Assert
+        assert!((reflected - 1.).abs() < 0.02, "{reflected}");
+        assert_eq!(attenuation, Color3::new(1., 1., 1.));
+    }
+
+    #[test]
+    fn metal_conserves_energy() {
+        // Arrange
+        seed_random(3);
+        let material: Rc<dyn Material> = Rc::new(Metal::new(Color3::new(1., 1., 1.), 0.5));
+        // At grazing incidence, part of the cone is below the surface and lost.
+        let (r, rec) = hit(material, unit(1., 0., 0.2));
+
+        // Act
+        let reflected = integrate(|d| rec.material.eval(&r, &rec, d).x);
+        let scattered = (0..SAMPLES)
+            .filter_map(|_| rec.material.scatter(&r, &rec))
+            .map(|(attenuation, _)| attenuation.x)
+            .sum::<f64>()
+            / f64::from(SAMPLES);
+
+        // Assert
+        assert!(scattered < 1.);
+        assert!(
+            (reflected - scattered).abs() < 0.05,
+            "{reflected} {scattered}"
+        );
+    }
+
+    #[test]
+    fn pdfs_integrate_to_one() {
+        // Arrange
+        seed_random(4);
+        let materials: [Rc<dyn Material>; 3] = [
+            Rc::new(Lambertian::new(Color3::new(0.5, 0.5, 0.5))),
+            Rc::new(Checker::new(
+                1.,
+                Color3::new(0.2, 0.2, 0.2),
+                Color3::new(0.8, 0.8, 0.8),
+            )),
+            Rc::new(Metal::new(Color3::new(0.5, 0.5, 0.5), 0.5)),
+        ];
+
+        for material in materials {
+            let (r, rec) = hit(material, unit(0.1, 0.2, 1.));
+
+            // Act
+            let total = integrate(|d| rec.material.pdf(&r, &rec, d));
+
+            // Assert
+            assert!((total - 1.).abs() < 0.05, "{total}");
+        }
+    }
+
+    /// Diffuse materials only, see `metal_eval_symmetric` for metal.
+    #[test]
+    fn reciprocity() {
+        // Arrange
+        seed_random(5);
+        let materials: [Rc<dyn Material>; 2] = [
+            Rc::new(Lambertian::new(Color3::new(0.5, 0.5, 0.5))),
+            Rc::new(Checker::new(
+                1.,
+                Color3::new(0.2, 0.2, 0.2),
+                Color3::new(0.8, 0.8, 0.8),
+            )),
+        ];
+
+        for material in materials {
+            for _ in 0..100 {
+                let a = uniform_sphere(random_2d());
+                let b = uniform_sphere(random_2d());
+                let (a, b) = (
+                    Vec3::new(a.x, a.y, a.z.abs()),
+                    Vec3::new(b.x, b.y, b.z.abs()),
+                );
+
+                // Act
+                // The BSDF, dividing out the cosine `eval` includes
+                let (r_a, rec_a) = hit(Rc::clone(&material), a);
+                let (r_b, rec_b) = hit(Rc::clone(&material), b);
+                let f_ab = rec_a.material.eval(&r_a, &rec_a, &b) / b.z;
+                let f_ba = rec_b.material.eval(&r_b, &rec_b, &a) / a.z;
+
+                // Assert
+                assert!((f_ab - f_ba).near_zero(), "{f_ab:?} {f_ba:?}");
+                assert!((f_ab - rec_a.material.albedo(&rec_a) / PI).near_zero());
+            }
+        }
+    }
+
+    /// Metal's `eval`, its density times the albedo, is symmetric, though its BSDF is not.
+    #[test]
+    fn metal_eval_symmetric() {
+        // Arrange
+        seed_random(6);
+        let material: Rc<dyn Material> = Rc::new(Metal::new(Color3::new(0.8, 0.6, 0.4), 0.5));
+
+        for _ in 0..100 {
+            let a = uniform_sphere(random_2d());
+            let a = Vec3::new(a.x, a.y, a.z.abs() + 0.1).unit_vector();
+            let (r_a, rec_a) = hit(Rc::clone(&material), a);
+            // Sample `b` from `a` so that the density is not zero.
+            let Some((_, scattered)) = rec_a.material.scatter(&r_a, &rec_a) else {
+                continue;
+            };
+            let b = scattered.direction.unit_vector();
+
+            // Act
+            let (r_b, rec_b) = hit(Rc::clone(&material), b);
+            let eval_ab = rec_a.material.eval(&r_a, &rec_a, &b);
+            let eval_ba = rec_b.material.eval(&r_b, &rec_b, &a);
+
+            // Assert
+            assert!(eval_ab.x > 0.);
+            assert!((eval_ab - eval_ba).near_zero(), "{eval_ab:?} {eval_ba:?}");
+        }
+    }
 }
@@ -0,0 +1,462 @@
+//! Image effects applied after rendering, either to the linear HDR image or to the display
+//! image after tone mapping.
+
+use std::{
+    fmt::Debug,
+    fs,
+    io::{self, ErrorKind},
+    path::Path,
+};
+
+use crate::{
+    color::{srgb_eotf, srgb_oetf},
+    image::Image,
+    sampler::pixel_hash,
+    vec3::Color3,
+};
+
+/// Point in the output pipeline an effect runs at.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum Stage {
+    /// On the linear rendered radiance, before the display transform
+    #[default]
+    Hdr,
+    /// On linear display colors in [0,1], after the display transform and before encoding
+    Display,
+}
+
+pub trait PostEffect: Debug {
+    fn apply(&self, image: &mut Image);
+
+    fn stage(&self) -> Stage {
+        Stage::Hdr
+    }
+}
+
+/// Glow around over-bright pixels, as scattered in a lens.
+#[derive(Clone, Copy, Debug)]
+pub struct Bloom {
+    /// Factor of the blurred light added back to the image
+    pub intensity: f64,
+    /// Standard deviation of the glow, in pixels; zero or less keeps the glow unblurred
+    pub radius: f64,
+    /// Luminance above which pixels glow, at least zero
+    pub threshold: f64,
+}
+
+impl Bloom {
+    pub fn new(threshold: f64, intensity: f64, radius: f64) -> Self {
+        Self {
+            intensity,
+            radius,
+            threshold,
+        }
+    }
+}
+
+impl PostEffect for Bloom {
+    fn apply(&self, image: &mut Image) {
+        let threshold = self.threshold.max(0.);
+        let mut bright = image.clone();
+        for pixel in bright.pixels_mut() {
+            let luminance = pixel.luminance();
+            *pixel = match luminance > threshold {
+                true => *pixel * ((luminance - threshold) / luminance),
+                false => Color3::default(),
+            };
+        }
+
+        let glow = gaussian_blur(&bright, self.radius);
+        for (pixel, glow) in image.pixels_mut().iter_mut().zip(glow.pixels()) {
+            *pixel += self.intensity * *glow;
+        }
+    }
+}
+
+/// Darkening towards the image corners, following the cos⁴ law of natural vignetting.
+#[derive(Clone, Copy, Debug)]
+pub struct Vignette {
+    /// Tangent of the angle off-axis at the corners; zero is no vignetting
+    pub strength: f64,
+}
+
+impl Vignette {
+    pub fn new(strength: f64) -> Self {
+        Self { strength }
+    }
+}
+
+impl PostEffect for Vignette {
+    fn apply(&self, image: &mut Image) {
+        let (cx, cy) = (
+            f64::from(image.width()) / 2.,
+            f64::from(image.height()) / 2.,
+        );
+        let half_diagonal = cx.hypot(cy);
+        for j in 0..image.height() {
+            for i in 0..image.width() {
+                let (x, y) = (f64::from(i) + 0.5 - cx, f64::from(j) + 0.5 - cy);
+                let tan_theta = self.strength * x.hypot(y) / half_diagonal;
+                let cos2 = 1. / (1. + tan_theta * tan_theta);
+                image.set(i, j, cos2 * cos2 * image.get(i, j));
+            }
+        }
+    }
+}
+
+/// Per-pixel noise in brightness, like the grain of film stock.
+#[derive(Clone, Copy, Debug)]
+pub struct FilmGrain {
+    /// Largest relative change of a pixel's brightness
+    pub amount: f64,
+    pub seed: u64,
+}
+
+impl FilmGrain {
+    pub fn new(amount: f64, seed: u64) -> Self {
+        Self { amount, seed }
+    }
+}
+
+impl PostEffect for FilmGrain {
+    fn apply(&self, image: &mut Image) {
+        for j in 0..image.height() {
+            for i in 0..image.width() {
+                let u = (pixel_hash((i, j), 0, self.seed) >> 11) as f64 / (1_u64 << 53) as f64;
+                let factor = 1. + self.amount * (2. * u - 1.);
+                image.set(i, j, factor * image.get(i, j));
+            }
+        }
+    }
+}
+
+/// Lateral color fringes, from red and blue being magnified differently around the center.
+///
+/// Unlike the camera's chromatic aberration, this shifts the finished image and so also
+/// works with few samples.
+#[derive(Clone, Copy, Debug)]
+pub struct ChromaticAberration {
+    /// Relative change of the image scale between the color channels
+    pub strength: f64,
+}
+
+impl ChromaticAberration {
+    pub fn new(strength: f64) -> Self {
+        Self { strength }
+    }
+}
+
+impl PostEffect for ChromaticAberration {
+    fn apply(&self, image: &mut Image) {
+        let source = image.clone();
+        let (cx, cy) = (
+            f64::from(image.width()) / 2.,
+            f64::from(image.height()) / 2.,
+        );
+        for j in 0..image.height() {
+            for i in 0..image.width() {
+                let (x, y) = (f64::from(i) + 0.5 - cx, f64::from(j) + 0.5 - cy);
+                // Red is scaled down and blue up, around green.
+                let sample = |scale: f64| bilinear(&source, cx + x / scale, cy + y / scale);
+                let red = sample(1. - self.strength).x;
+                let blue = sample(1. + self.strength).z;
+                let green = image.get(i, j).y;
+                image.set(i, j, Color3::new(red, green, blue));
+            }
+        }
+    }
+}
+
+/// Color grading with a 3D lookup table, read from an Adobe/Resolve `.cube` file.
+///
+/// The table is indexed with sRGB encoded colors, as most grading tools export it.
+#[derive(Clone, Debug)]
+pub struct Lut {
+    domain_min: Color3,
+    domain_max: Color3,
+    size: usize,
+    /// Output colors with red changing fastest, then green, then blue
+    table: Vec<Color3>,
+}
+
+impl Lut {
+    pub fn load(path: impl AsRef<Path>) -> io::Result<Self> {
+        Self::parse(&fs::read_to_string(path)?)
+    }
+
+    pub fn parse(text: &str) -> io::Result<Self> {
+        let invalid = |msg: &str| io::Error::new(ErrorKind::InvalidData, msg.to_owned());
+        let triple = |words: &[&str]| -> io::Result<Color3> {
+            let v = words
+                .iter()
+                .map(|w| w.parse::<f64>().map_err(|_| invalid("invalid number")))
+                .collect::<io::Result<Vec<_>>>()?;
+            match *v {
+                [r, g, b] => Ok(Color3::new(r, g, b)),
+                _ => Err(invalid("expected three numbers")),
+            }
+        };
+
+        let mut size = None;
+        let mut domain_min = Color3::new(0., 0., 0.);
+        let mut domain_max = Color3::new(1., 1., 1.);
+        let mut table = Vec::new();
+        for line in text.lines() {
+            let words = line.split_whitespace().collect::<Vec<_>>();
+            match words.as_slice() {
+                [] => {}
+                [comment, ..] if comment.starts_with('#') => {}
+                ["TITLE", ..] => {}
+                ["LUT_1D_SIZE", ..] => return Err(invalid("1D LUTs are not supported")),
+                ["LUT_3D_SIZE", n] => {
+                    size = Some(
+                        n.parse::<usize>()
+                            .map_err(|_| invalid("invalid LUT size"))?,
+                    )
+                }
+                ["DOMAIN_MIN", rest @ ..] => domain_min = triple(rest)?,
+                ["DOMAIN_MAX", rest @ ..] => domain_max = triple(rest)?,
+                _ => table.push(triple(&words)?),
+            }
+        }
+
+        let size = size.ok_or_else(|| invalid("missing LUT_3D_SIZE"))?;
+        let entries = size
+            .checked_mul(size)
+            .and_then(|n| n.checked_mul(size))
+            .ok_or_else(|| invalid("LUT size is too large"))?;
+        if size < 2 || table.len() != entries {
+            return Err(invalid("LUT size doesn't match its entries"));
+        }
+        Ok(Self {
+            domain_min,
+            domain_max,
+            size,
+            table,
+        })
+    }
+
+    /// Returns the graded color, interpolated trilinearly between the table entries.
+    pub fn lookup(&self, color: Color3) -> Color3 {
+        let n = self.size - 1;
+        let position = |v: f64, min: f64, max: f64| {
+            let x = ((v - min) / (max - min)).clamp(0., 1.) * n as f64;
+            let i = (x.floor() as usize).min(n - 1);
+            (i, x - i as f64)
+        };
+        let (r, fr) = position(color.x, self.domain_min.x, self.domain_max.x);
+        let (g, fg) = position(color.y, self.domain_min.y, self.domain_max.y);
+        let (b, fb) = position(color.z, self.domain_min.z, self.domain_max.z);
+
+        let entry = |dr, dg, db| {
+            self.table[(b + db) * self.size * self.size + (g + dg) * self.size + r + dr]
+        };
+        let lerp = |a: Color3, b: Color3, t: f64| (1. - t) * a + t * b;
+        let along_r = |dg, db| lerp(entry(0, dg, db), entry(1, dg, db), fr);
+        let along_g = |db| lerp(along_r(0, db), along_r(1, db), fg);
+        lerp(along_g(0), along_g(1), fb)
+    }
+}
+
+impl PostEffect for Lut {
+    fn apply(&self, image: &mut Image) {
+        let encode = |c: Color3| Color3::new(srgb_oetf(c.x), srgb_oetf(c.y), srgb_oetf(c.z));
+        let decode = |c: Color3| Color3::new(srgb_eotf(c.x), srgb_eotf(c.y), srgb_eotf(c.z));
+        for pixel in image.pixels_mut() {
+            *pixel = decode(self.lookup(encode(*pixel)));
+        }
+    }
+
+    fn stage(&self) -> Stage {
+        Stage::Display
+    }
+}
+
+/// Separable Gaussian blur with standard deviation `sigma` in pixels, clamping at the edges.
+///
+/// A `sigma` of zero or less leaves the image as it is.
+fn gaussian_blur(image: &Image, sigma: f64) -> Image {
+    if sigma.is_nan() || sigma <= 0. {
+        return image.clone();
+    }
+    let (width, height) = (image.width() as i64, image.height() as i64);
+    // Taps further out than the image size only repeat its edge pixels.
+    let radius = ((3. * sigma).ceil() as i64).min(width.max(height));
+    let weights = (-radius..=radius)
+        .map(|d| (-(d * d) as f64 / (2. * sigma * sigma)).exp())
+        .collect::<Vec<_>>();
+    let total = weights.iter().sum::<f64>();
+
+    let pass = |source: &Image, horizontal: bool| {
+        let mut out = Image::new(source.width(), source.height());
+        for j in 0..height {
+            for i in 0..width {
+                let mut sum = Color3::default();
+                for (d, w) in (-radius..=radius).zip(&weights) {
+                    let (x, y) = match horizontal {
+                        true => ((i + d).clamp(0, width - 1), j),
+                        false => (i, (j + d).clamp(0, height - 1)),
+                    };
+                    sum += *w * source.get(x as _, y as _);
+                }
+                out.set(i as _, j as _, sum / total);
+            }
+        }
+        out
+    };
+    pass(&pass(image, true), false)
+}
+
+/// Returns the color at raster position x,y, interpolated between pixel centers.
+fn bilinear(image: &Image, x: f64, y: f64) -> Color3 {
+    let (max_i, max_j) = (image.width() - 1, image.height() - 1);
+    let (x, y) = ((x - 0.5).max(0.), (y - 0.5).max(0.));
+    let (i, j) = ((x as u32).min(max_i), (y as u32).min(max_j));
+    let (fx, fy) = ((x - f64::from(i)).min(1.), (y - f64::from(j)).min(1.));
+    let (i1, j1) = ((i + 1).min(max_i), (j + 1).min(max_j));
+
+    let top = (1. - fx) * image.get(i, j) + fx * image.get(i1, j);
+    let bottom = (1. - fx) * image.get(i, j1) + fx * image.get(i1, j1);
+    (1. - fy) * top + fy * bottom
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn lut() {
+        // Arrange
+        let text = "TITLE \"invert\"\n# comment\nLUT_3D_SIZE 2\n\
+            1 1 1\n0 1 1\n1 0 1\n0 0 1\n1 1 0\n0 1 0\n1 0 0\n0 0 0\n";
+
+        // Act
+        let lut = Lut::parse(text).unwrap();
+
+        // Assert
+        assert_eq!(lut.lookup(Color3::new(0., 0., 0.)), Color3::new(1., 1., 1.));
+        assert_eq!(lut.lookup(Color3::new(1., 0., 0.)), Color3::new(0., 1., 1.));
+        assert!(
+            (lut.lookup(Color3::new(0.25, 0.5, 0.75)) - Color3::new(0.75, 0.5, 0.25)).near_zero()
+        );
+        assert!(Lut::parse("LUT_3D_SIZE 2\n0 0 0\n").is_err());
+        let huge = Lut::parse("LUT_3D_SIZE 18446744073709551615\n0 0 0\n").unwrap_err();
+        assert_eq!(huge.kind(), ErrorKind::InvalidData);
+    }
+
+    #[test]
+    fn bloom() {
+        // Arrange
+        let mut image = Image::new(9, 9);
+        image.set(4, 4, Color3::new(10., 10., 10.));
+        image.set(0, 0, Color3::new(0.5, 0.5, 0.5));
+
+        // Act
+        Bloom::new(1., 1., 1.).apply(&mut image);
+
+        // Assert
+        assert!(image.get(5, 4).x > 0.);
+        assert!(image.get(4, 4).x > 10.);
+        assert_eq!(image.get(8, 8), Color3::new(0., 0., 0.));
+        assert!((image.get(0, 0).x - 0.5).abs() < 1e-12);
+    }
+
+    #[test]
+    fn bloom_degenerate() {
+        // Arrange
+        let mut image = Image::new(3, 3);
+        image.set(1, 1, Color3::new(2., 2., 2.));
+
+        // Act
+        Bloom::new(0., 1., 0.).apply(&mut image);
+        Bloom::new(-1., 1., -2.).apply(&mut image);
+
+        // Assert
+        assert!(image.pixels().iter().all(|p| p.x.is_finite()));
+        assert_eq!(image.get(0, 0), Color3::new(0., 0., 0.));
+        assert!((image.get(1, 1).x - 8.).abs() < 1e-12);
+    }
+
+    #[test]
+    fn bloom_wide() {
+        // Arrange
+        let mut image = Image::new(3, 3);
+        image.set(1, 1, Color3::new(2., 2., 2.));
+
+        // Act
+        Bloom::new(1., 1., 1e12).apply(&mut image);
+
+        // Assert
+        assert!(image.pixels().iter().all(|p| p.x.is_finite() && p.x > 0.));
+    }
+
+    #[test]
+    fn vignette() {
+        // Arrange
+        let mut image = Image::new(10, 10);
+        image.pixels_mut().fill(Color3::new(1., 1., 1.));
+
+        // Act
+        Vignette::new(0.5).apply(&mut image);
+
+        // Assert
+        // The corner pixel's center is 0.9 of the way to the corner.
+        let tan_theta: f64 = 0.5 * 0.9;
+        let falloff = (1. + tan_theta * tan_theta).powi(-2);
+        assert!((image.get(0, 0).x - falloff).abs() < 1e-12);
+        assert!((image.get(9, 9).x - falloff).abs() < 1e-12);
+        assert!(image.get(4, 4).x > 0.99);
+    }
+
+    #[test]
+    fn film_grain() {
+        // Arrange
+        let mut image = Image::new(64, 64);
+        image.pixels_mut().fill(Color3::new(0.5, 0.5, 0.5));
+        let amount = 0.1;
+
+        // Act
+        FilmGrain::new(amount, 1).apply(&mut image);
+
+        // Assert
+        let pixels = image.pixels();
+        for p in pixels {
+            assert!((p.x - 0.5).abs() <= 0.5 * amount, "{p:?}");
+            assert_eq!((p.x, p.x), (p.y, p.z));
+        }
+        let mean = pixels.iter().map(|p| p.x).sum::<f64>() / pixels.len() as f64;
+        assert!((mean - 0.5).abs() < 0.002, "{mean}");
+        assert!(pixels.iter().any(|p| p.x != 0.5));
+    }
+
+    #[test]
+    fn chromatic_aberration() {
+        // Arrange
+        let mut image = Image::new(21, 1);
+        image.set(15, 0, Color3::new(1., 1., 1.));
+        let source = image.clone();
+
+        // Act
+        ChromaticAberration::new(0.1).apply(&mut image);
+
+        // Assert
+        let centroid = |channel: fn(&Color3) -> f64| {
+            let (mut sum, mut weight) = (0., 0.);
+            for (i, p) in image.pixels().iter().enumerate() {
+                sum += i as f64 * channel(p);
+                weight += channel(p);
+            }
+            sum / weight
+        };
+        let (red, green, blue) = (centroid(|p| p.x), centroid(|p| p.y), centroid(|p| p.z));
+        assert_eq!(green, 15.);
+        assert!(image
+            .pixels()
+            .iter()
+            .zip(source.pixels())
+            .all(|(a, b)| a.y == b.y));
+        // Red is pulled towards the center and blue pushed out, by about the strength.
+        assert!((red - 14.5).abs() < 0.1, "{red}");
+        assert!((blue - 15.5).abs() < 0.1, "{blue}");
+    }
+}
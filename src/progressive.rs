@@ -0,0 +1,111 @@
+use std::{path::PathBuf, time::Duration};
+
+/// Settings to render the whole image in passes of a few samples per pixel.
+#[derive(Clone, Debug)]
+pub struct Progressive {
+    /// Count of samples added to every pixel in each pass
+    pub pass_samples: u32,
+    /// Path to write the intermediate image to, and the interval between writes
+    pub snapshots: Option<(PathBuf, Duration)>,
+    /// Count of samples per pixel after which rendering stops
+    pub target_samples: Option<u32>,
+    /// Wall-clock time after which rendering stops
+    pub time_budget: Option<Duration>,
+}
+
+impl Progressive {
+    /// Create settings for passes of `pass_samples`.
+    ///
+    /// Without a time budget or target, rendering stops at the camera's
+    /// `samples_per_pixel`.
+    pub fn new(pass_samples: u32) -> Self {
+        Self {
+            pass_samples: pass_samples.max(1),
+            snapshots: None,
+            target_samples: None,
+            time_budget: None,
+        }
+    }
+
+    pub fn with_snapshots(mut self, path: impl Into<PathBuf>, interval: Duration) -> Self {
+        self.snapshots = Some((path.into(), interval));
+        self
+    }
+
+    pub fn with_target_samples(mut self, target_samples: u32) -> Self {
+        self.target_samples = Some(target_samples);
+        self
+    }
+
+    pub fn with_time_budget(mut self, time_budget: Duration) -> Self {
+        self.time_budget = Some(time_budget);
+        self
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::rc::Rc;
+
+    use super::*;
+    use crate::{
+        camera::Camera,
+        hittable::Sphere,
+        image::Image,
+        integrator::DebugIntegrator,
+        material::Lambertian,
+        sampler::SobolSampler,
+        scene::Scene,
+        vec3::{Color3, Point3},
+    };
+
+    /// Render a sphere's normals with `samples_per_pixel`, progressively if `progressive` is
+    /// set. With a Sobol sampler, pixels get the same samples whatever the pass order.
+    fn render(samples_per_pixel: u32, progressive: Option<Progressive>) -> Image {
+        let mut scene = Scene::new();
+        let material = Rc::new(Lambertian::new(Color3::new(0.5, 0.5, 0.5)));
+        scene.add(Sphere::new(Point3::new(0.3, 0.2, -2.), 1., material));
+        let camera = Camera::builder()
+            .integrator(DebugIntegrator::default())
+            .resolution(8, 6)
+            .sampler(SobolSampler::new(samples_per_pixel, 0))
+            .build()
+            .unwrap();
+        match progressive {
+            Some(progressive) => camera.render_progressive_image(&scene, &progressive),
+            None => camera.render_image(&scene),
+        }
+        .unwrap()
+    }
+
+    fn assert_near(a: &Image, b: &Image) {
+        for (a, b) in a.pixels().iter().zip(b.pixels()) {
+            assert!((*a - *b).near_zero(), "{a:?} vs {b:?}");
+        }
+    }
+
+    #[test]
+    fn target_samples() {
+        // Arrange
+        let progressive = Progressive::new(3).with_target_samples(8);
+
+        // Act
+        let image = render(64, Some(progressive));
+
+        // Assert
+        assert_near(&image, &render(8, None));
+    }
+
+    #[test]
+    fn time_budget() {
+        // Arrange
+        let progressive = Progressive::new(2).with_time_budget(Duration::ZERO);
+
+        // Act
+        let image = render(64, Some(progressive));
+
+        // Assert
+        // One whole pass, however long it took
+        assert_near(&image, &render(2, None));
+    }
+}
@@ -1,14 +1,38 @@
 use crate::vec3::{Point3, Vec3};
 
-#[derive(Debug)]
+/// What a ray is traced for, which decides the objects it sees.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum RayKind {
+    /// From the camera through a pixel
+    Camera,
+    /// Scattered diffusely, or traced for lighting rather than a view of the scene
+    #[default]
+    Diffuse,
+    /// Reflected off a mirror, glossy or glass surface
+    Reflection,
+    /// Transmitted through a glass surface
+    Refraction,
+}
+
+#[derive(Clone, Debug)]
 pub struct Ray {
     pub origin: Point3,
     pub direction: Vec3,
+    pub kind: RayKind,
 }
 
 impl Ray {
     pub const fn new(origin: Point3, direction: Vec3) -> Self {
-        Self { origin, direction }
+        Self {
+            origin,
+            direction,
+            kind: RayKind::Diffuse,
+        }
+    }
+
+    pub const fn with_kind(mut self, kind: RayKind) -> Self {
+        self.kind = kind;
+        self
     }
 
     pub fn at(&self, t: f64) -> Point3 {
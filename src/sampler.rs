@@ -0,0 +1,433 @@
+//! Per-pixel sample sequences for camera rays.
+//!
+//! A [`Sampler`] is stateless: the value for a given pixel, sample index and dimension is
+//! always the same, so pixels can be rendered in any order. Use a [`PixelSample`] to walk
+//! through the dimensions of one sample.
+
+use std::fmt::Debug;
+
+use crate::util::random_double;
+
+/// Largest `f64` below one, to keep samples in [0,1).
+const ONE_MINUS_EPSILON: f64 = 1. - f64::EPSILON / 2.;
+
+pub trait Sampler: Debug {
+    fn samples_per_pixel(&self) -> u32;
+
+    /// Returns coordinate `dim` of sample `index` in `pixel`.
+    fn get_1d(&self, pixel: (u32, u32), index: u32, dim: u32) -> f64;
+
+    /// Returns coordinates `dim` and `dim + 1` of sample `index` in `pixel`.
+    fn get_2d(&self, pixel: (u32, u32), index: u32, dim: u32) -> (f64, f64) {
+        (
+            self.get_1d(pixel, index, dim),
+            self.get_1d(pixel, index, dim + 1),
+        )
+    }
+}
+
+/// Cursor over the dimensions of one sample of one pixel.
+pub struct PixelSample<'a> {
+    sampler: &'a dyn Sampler,
+    pixel: (u32, u32),
+    index: u32,
+    dim: u32,
+}
+
+impl<'a> PixelSample<'a> {
+    pub fn new(sampler: &'a dyn Sampler, pixel: (u32, u32), index: u32) -> Self {
+        Self {
+            sampler,
+            pixel,
+            index,
+            dim: 0,
+        }
+    }
+
+    pub fn get_1d(&mut self) -> f64 {
+        let u = self.sampler.get_1d(self.pixel, self.index, self.dim);
+        self.dim += 1;
+        u
+    }
+
+    pub fn get_2d(&mut self) -> (f64, f64) {
+        let u = self.sampler.get_2d(self.pixel, self.index, self.dim);
+        self.dim += 2;
+        u
+    }
+}
+
+/// Uniform random samples without any correlation between them.
+#[derive(Debug)]
+pub struct IndependentSampler {
+    samples_per_pixel: u32,
+}
+
+impl IndependentSampler {
+    pub fn new(samples_per_pixel: u32) -> Self {
+        Self { samples_per_pixel }
+    }
+}
+
+impl Sampler for IndependentSampler {
+    fn samples_per_pixel(&self) -> u32 {
+        self.samples_per_pixel
+    }
+
+    fn get_1d(&self, _pixel: (u32, u32), _index: u32, _dim: u32) -> f64 {
+        random_double()
+    }
+
+    fn get_2d(&self, _pixel: (u32, u32), _index: u32, _dim: u32) -> (f64, f64) {
+        (random_double(), random_double())
+    }
+}
+
+/// Jittered samples, one per stratum, with strata visited in a random order per pixel and
+/// dimension.
+///
+/// 2D samples are stratified on the largest grid of `x_strata` × `y_strata` cells that
+/// `samples_per_pixel` fills. Samples left over when it is not a product of the two are
+/// jittered across the whole pixel, so every cell is sampled.
+#[derive(Debug)]
+pub struct StratifiedSampler {
+    samples_per_pixel: u32,
+    x_strata: u32,
+    y_strata: u32,
+}
+
+impl StratifiedSampler {
+    pub fn new(samples_per_pixel: u32) -> Self {
+        let samples_per_pixel = samples_per_pixel.max(1);
+        let x_strata = (f64::from(samples_per_pixel).sqrt() as u32).max(1);
+        let y_strata = samples_per_pixel / x_strata;
+        Self {
+            samples_per_pixel,
+            x_strata,
+            y_strata,
+        }
+    }
+}
+
+impl Sampler for StratifiedSampler {
+    fn samples_per_pixel(&self) -> u32 {
+        self.samples_per_pixel
+    }
+
+    fn get_1d(&self, pixel: (u32, u32), index: u32, dim: u32) -> f64 {
+        let hash = pixel_hash(pixel, dim, 0);
+        let stratum = permutation_element(index, self.samples_per_pixel, hash as u32);
+        let u = (f64::from(stratum) + random_double()) / f64::from(self.samples_per_pixel);
+        u.min(ONE_MINUS_EPSILON)
+    }
+
+    fn get_2d(&self, pixel: (u32, u32), index: u32, dim: u32) -> (f64, f64) {
+        let hash = pixel_hash(pixel, dim, 0);
+        let stratum = permutation_element(index, self.samples_per_pixel, hash as u32);
+        if stratum >= self.x_strata * self.y_strata {
+            return (random_double(), random_double());
+        }
+        let x = stratum % self.x_strata;
+        let y = stratum / self.x_strata;
+        let u = (f64::from(x) + random_double()) / f64::from(self.x_strata);
+        let v = (f64::from(y) + random_double()) / f64::from(self.y_strata);
+        (u.min(ONE_MINUS_EPSILON), v.min(ONE_MINUS_EPSILON))
+    }
+}
+
+/// Owen-scrambled Halton sequence, with an independent scramble for each pixel.
+#[derive(Debug)]
+pub struct HaltonSampler {
+    samples_per_pixel: u32,
+    seed: u64,
+}
+
+impl HaltonSampler {
+    pub fn new(samples_per_pixel: u32, seed: u64) -> Self {
+        Self {
+            samples_per_pixel,
+            seed,
+        }
+    }
+}
+
+impl Sampler for HaltonSampler {
+    fn samples_per_pixel(&self) -> u32 {
+        self.samples_per_pixel
+    }
+
+    fn get_1d(&self, pixel: (u32, u32), index: u32, dim: u32) -> f64 {
+        match PRIMES.get(dim as usize) {
+            Some(&base) => {
+                let hash = pixel_hash(pixel, dim, self.seed) as u32;
+                owen_scrambled_radical_inverse(base, index.into(), hash)
+            }
+            None => random_double(),
+        }
+    }
+}
+
+/// Owen-scrambled Sobol sequence, with an independent scramble and sample order for each
+/// pixel.
+///
+/// Works best when `samples_per_pixel` is a power of two.
+#[derive(Debug)]
+pub struct SobolSampler {
+    samples_per_pixel: u32,
+    seed: u64,
+    matrices: Vec<[u32; 32]>,
+}
+
+impl SobolSampler {
+    pub fn new(samples_per_pixel: u32, seed: u64) -> Self {
+        Self {
+            samples_per_pixel,
+            seed,
+            matrices: sobol_matrices(),
+        }
+    }
+}
+
+impl Sampler for SobolSampler {
+    fn samples_per_pixel(&self) -> u32 {
+        self.samples_per_pixel
+    }
+
+    fn get_1d(&self, pixel: (u32, u32), index: u32, dim: u32) -> f64 {
+        let Some(matrix) = self.matrices.get(dim as usize) else {
+            return random_double();
+        };
+
+        // Shuffle the sample order per pixel, so neighbouring pixels don't see the same
+        // points in the same order.
+        let index = nested_uniform_scramble(index, pixel_hash(pixel, u32::MAX, self.seed) as u32);
+        let hash = pixel_hash(pixel, dim, self.seed) as u32;
+        let bits = nested_uniform_scramble(sobol_sample(matrix, index), hash);
+        (f64::from(bits) * 2_f64.powi(-32)).min(ONE_MINUS_EPSILON)
+    }
+}
+
+const PRIMES: [u32; 32] = [
+    2, 3, 5, 7, 11, 13, 17, 19, 23, 29, 31, 37, 41, 43, 47, 53, 59, 61, 67, 71, 73, 79, 83, 89, 97,
+    101, 103, 107, 109, 113, 127, 131,
+];
+
+/// Sobol initialization data `(s, a, m)` from Joe and Kuo for dimensions after the first.
+const SOBOL_INIT: [(u32, u32, &[u32]); 12] = [
+    (1, 0, &[1]),
+    (2, 1, &[1, 3]),
+    (3, 1, &[1, 3, 1]),
+    (3, 2, &[1, 1, 1]),
+    (4, 1, &[1, 1, 3, 3]),
+    (4, 4, &[1, 3, 5, 13]),
+    (5, 2, &[1, 1, 5, 5, 17]),
+    (5, 4, &[1, 1, 5, 5, 5]),
+    (5, 7, &[1, 1, 7, 11, 19]),
+    (5, 11, &[1, 1, 5, 1, 1]),
+    (5, 13, &[1, 1, 1, 3, 11]),
+    (5, 14, &[1, 3, 5, 5, 31]),
+];
+
+/// Returns the direction numbers of each supported Sobol dimension.
+fn sobol_matrices() -> Vec<[u32; 32]> {
+    let mut matrices = Vec::with_capacity(SOBOL_INIT.len() + 1);
+
+    // The first dimension is the van der Corput sequence in base 2.
+    matrices.push(std::array::from_fn(|k| 1 << (31 - k)));
+
+    for (s, a, m) in SOBOL_INIT {
+        let s = s as usize;
+        let mut v = [0_u32; 32];
+        for k in 0..32 {
+            v[k] = match k < s {
+                true => m[k] << (31 - k),
+                false => {
+                    let mut vk = v[k - s] ^ (v[k - s] >> s);
+                    for i in 1..s {
+                        if (a >> (s - 1 - i)) & 1 == 1 {
+                            vk ^= v[k - i];
+                        }
+                    }
+                    vk
+                }
+            };
+        }
+        matrices.push(v);
+    }
+    matrices
+}
+
+fn sobol_sample(matrix: &[u32; 32], mut index: u32) -> u32 {
+    let mut bits = 0;
+    let mut k = 0;
+    while index != 0 {
+        if index & 1 == 1 {
+            bits ^= matrix[k];
+        }
+        index >>= 1;
+        k += 1;
+    }
+    bits
+}
+
+/// Radical inverse of `a` in `base`, with the digits permuted depending on `hash` and the
+/// digits before them.
+fn owen_scrambled_radical_inverse(base: u32, mut a: u64, hash: u32) -> f64 {
+    let base64 = u64::from(base);
+    let inv_base = 1. / f64::from(base);
+    let mut inv_base_m = 1.;
+    let mut reversed: u64 = 0;
+
+    // Continue past the last non-zero digit, as long as further digits still change the
+    // result in double precision.
+    while 1. - (f64::from(base) - 1.) * inv_base_m < 1. {
+        let next = a / base64;
+        let digit = (a - next * base64) as u32;
+        let digit_hash = mix_bits(u64::from(hash) ^ reversed) as u32;
+        let digit = permutation_element(digit, base, digit_hash);
+        reversed = reversed * base64 + u64::from(digit);
+        inv_base_m *= inv_base;
+        a = next;
+    }
+    (inv_base_m * reversed as f64).min(ONE_MINUS_EPSILON)
+}
+
+/// Owen scrambling of the bits of `v`, by Laine and Karras.
+fn nested_uniform_scramble(v: u32, seed: u32) -> u32 {
+    let mut v = v.reverse_bits();
+    v = v.wrapping_add(seed);
+    v ^= v.wrapping_mul(0x6c50b47c);
+    v ^= v.wrapping_mul(0xb82f1e52);
+    v ^= v.wrapping_mul(0xc7afe638);
+    v ^= v.wrapping_mul(0x8d22f6e6);
+    v.reverse_bits()
+}
+
+/// Returns element `i` of a random permutation of `0..l` selected by `p`.
+///
+/// From Kensler, "Correlated Multi-Jittered Sampling".
+fn permutation_element(mut i: u32, l: u32, p: u32) -> u32 {
+    let mut w = l - 1;
+    w |= w >> 1;
+    w |= w >> 2;
+    w |= w >> 4;
+    w |= w >> 8;
+    w |= w >> 16;
+    loop {
+        i ^= p;
+        i = i.wrapping_mul(0xe170893d);
+        i ^= p >> 16;
+        i ^= (i & w) >> 4;
+        i ^= p >> 8;
+        i = i.wrapping_mul(0x0929eb3f);
+        i ^= p >> 23;
+        i ^= (i & w) >> 1;
+        i = i.wrapping_mul(1 | p >> 27);
+        i = i.wrapping_mul(0x6935fa69);
+        i ^= (i & w) >> 11;
+        i = i.wrapping_mul(0x74dcb303);
+        i ^= (i & w) >> 2;
+        i = i.wrapping_mul(0x9e501cc3);
+        i ^= (i & w) >> 2;
+        i = i.wrapping_mul(0xc860a3df);
+        i &= w;
+        i ^= i >> 5;
+        if i < l {
+            break;
+        }
+    }
+    i.wrapping_add(p) % l
+}
+
+pub(crate) fn pixel_hash(pixel: (u32, u32), dim: u32, seed: u64) -> u64 {
+    let p = u64::from(pixel.0) | u64::from(pixel.1) << 32;
+    mix_bits(p ^ mix_bits(u64::from(dim) ^ mix_bits(seed)))
+}
+
+fn mix_bits(mut v: u64) -> u64 {
+    v ^= v >> 31;
+    v = v.wrapping_mul(0x7fb5d329728ea185);
+    v ^= v >> 27;
+    v = v.wrapping_mul(0x81dadef4bc2dd44d);
+    v ^= v >> 33;
+    v
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn sobol_sample() {
+        // Arrange
+        let matrices = sobol_matrices();
+
+        // Act
+        let points = (0..4)
+            .map(|i| {
+                let x = super::sobol_sample(&matrices[0], i);
+                let y = super::sobol_sample(&matrices[1], i);
+                (
+                    f64::from(x) * 2_f64.powi(-32),
+                    f64::from(y) * 2_f64.powi(-32),
+                )
+            })
+            .collect::<Vec<_>>();
+
+        // Assert
+        let result = vec![(0., 0.), (0.5, 0.5), (0.25, 0.75), (0.75, 0.25)];
+        assert_eq!(points, result);
+    }
+
+    #[test]
+    fn owen_scrambled_radical_inverse() {
+        // Arrange
+        let base = 3;
+
+        // Act
+        let mut strata = (0..9)
+            .map(|i| (super::owen_scrambled_radical_inverse(base, i, 42) * 9.) as u32)
+            .collect::<Vec<_>>();
+
+        // Assert
+        strata.sort();
+        assert_eq!(strata, (0..9).collect::<Vec<_>>());
+    }
+
+    #[test]
+    fn stratified_sampler() {
+        // Arrange
+        let sampler = StratifiedSampler::new(16);
+
+        // Act
+        let mut strata = (0..16)
+            .map(|i| {
+                let (u, v) = sampler.get_2d((3, 7), i, 0);
+                (v * 4.) as u32 * 4 + (u * 4.) as u32
+            })
+            .collect::<Vec<_>>();
+
+        // Assert
+        strata.sort();
+        assert_eq!(strata, (0..16).collect::<Vec<_>>());
+    }
+
+    #[test]
+    fn stratified_sampler_uneven() {
+        // Arrange
+        let sampler = StratifiedSampler::new(11);
+
+        // Act
+        let mut strata = (0..11)
+            .map(|i| {
+                let (u, v) = sampler.get_2d((3, 7), i, 0);
+                (v * 3.) as u32 * 3 + (u * 3.) as u32
+            })
+            .collect::<Vec<_>>();
+
+        // Assert
+        strata.sort();
+        strata.dedup();
+        assert_eq!(strata, (0..9).collect::<Vec<_>>());
+    }
+}
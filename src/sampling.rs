@@ -0,0 +1,142 @@
+//! Warping functions from uniform samples in [0,1)² onto common sampling domains.
+//!
+//! Directions returned by the hemisphere and cone functions are in a local frame with
+//! `z` as the "up" axis; use an [`Onb`] to bring them into world space.
+
+use std::f64::consts::{FRAC_PI_2, FRAC_PI_4, PI};
+
+use crate::{util::random_double, vec3::Vec3};
+
+/// Returns a pair of independent random reals in [0,1).
+pub fn random_2d() -> (f64, f64) {
+    (random_double(), random_double())
+}
+
+/// Maps a point in [0,1)² onto the unit disk in the xy-plane, preserving relative areas.
+///
+/// This is the concentric mapping by Shirley and Chiu, which keeps strata intact and
+/// has lower distortion than the polar mapping.
+pub fn concentric_disk(u: (f64, f64)) -> Vec3 {
+    // Map to [-1,1]² and handle the degenerate center.
+    let ox = 2. * u.0 - 1.;
+    let oy = 2. * u.1 - 1.;
+    if ox == 0. && oy == 0. {
+        return Vec3::new(0., 0., 0.);
+    }
+
+    let (r, theta) = match ox.abs() > oy.abs() {
+        true => (ox, FRAC_PI_4 * (oy / ox)),
+        false => (oy, FRAC_PI_2 - FRAC_PI_4 * (ox / oy)),
+    };
+    Vec3::new(r * theta.cos(), r * theta.sin(), 0.)
+}
+
+/// Returns a direction on the `z`-up hemisphere, distributed proportional to `cos θ`.
+pub fn cosine_hemisphere(u: (f64, f64)) -> Vec3 {
+    let d = concentric_disk(u);
+    let z = (1. - d.x * d.x - d.y * d.y).max(0.).sqrt();
+    Vec3::new(d.x, d.y, z)
+}
+
+pub fn cosine_hemisphere_pdf(cos_theta: f64) -> f64 {
+    cos_theta.max(0.) / PI
+}
+
+/// Returns a direction uniformly distributed on the unit sphere.
+pub fn uniform_sphere(u: (f64, f64)) -> Vec3 {
+    let z = 1. - 2. * u.0;
+    let r = (1. - z * z).max(0.).sqrt();
+    let phi = 2. * PI * u.1;
+    Vec3::new(r * phi.cos(), r * phi.sin(), z)
+}
+
+pub fn uniform_sphere_pdf() -> f64 {
+    1. / (4. * PI)
+}
+
+/// Returns a direction uniformly distributed in the cone around `z` with the half-angle
+/// whose cosine is `cos_theta_max`.
+pub fn uniform_cone(u: (f64, f64), cos_theta_max: f64) -> Vec3 {
+    let cos_theta = (1. - u.0) + u.0 * cos_theta_max;
+    let sin_theta = (1. - cos_theta * cos_theta).max(0.).sqrt();
+    let phi = 2. * PI * u.1;
+    Vec3::new(sin_theta * phi.cos(), sin_theta * phi.sin(), cos_theta)
+}
+
+pub fn uniform_cone_pdf(cos_theta_max: f64) -> f64 {
+    1. / (2. * PI * (1. - cos_theta_max))
+}
+
+/// Orthonormal basis with `w` pointing along a given direction.
+#[derive(Clone, Copy, Debug)]
+pub struct Onb {
+    pub u: Vec3,
+    pub v: Vec3,
+    pub w: Vec3,
+}
+
+impl Onb {
+    /// Build a basis around `n`, which does not need to have unit length.
+    pub fn new(n: &Vec3) -> Self {
+        // Branchless construction by Duff et al., "Building an Orthonormal Basis, Revisited".
+        let w = n.unit_vector();
+        let sign = 1_f64.copysign(w.z);
+        let a = -1. / (sign + w.z);
+        let b = w.x * w.y * a;
+        let u = Vec3::new(1. + sign * w.x * w.x * a, sign * b, -sign * w.x);
+        let v = Vec3::new(b, sign + w.y * w.y * a, -w.y);
+        Self { u, v, w }
+    }
+
+    /// Transform a vector from the local frame into world space.
+    pub fn local(&self, a: &Vec3) -> Vec3 {
+        a.x * self.u + a.y * self.v + a.z * self.w
+    }
+
+    /// Transform a world-space vector into the local frame.
+    pub fn to_local(&self, a: &Vec3) -> Vec3 {
+        Vec3::new(a.dot(&self.u), a.dot(&self.v), a.dot(&self.w))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn concentric_disk() {
+        // Arrange
+        let corners = [
+            (0., 0.),
+            (0.999, 0.),
+            (0., 0.999),
+            (0.999, 0.999),
+            (0.5, 0.5),
+        ];
+
+        // Act
+        let points = corners.map(super::concentric_disk);
+
+        // Assert
+        for p in points {
+            assert!(p.length() <= 1.);
+            assert_eq!(p.z, 0.);
+        }
+    }
+
+    #[test]
+    fn onb() {
+        // Arrange
+        let n = Vec3::new(0.3, -2., 0.7);
+
+        // Act
+        let uvw = Onb::new(&n);
+
+        // Assert
+        assert!((uvw.u.length() - 1.).abs() < 1e-12);
+        assert!((uvw.v.length() - 1.).abs() < 1e-12);
+        assert!(uvw.u.dot(&uvw.v).abs() < 1e-12);
+        assert!(uvw.u.dot(&uvw.w).abs() < 1e-12);
+        assert!((uvw.local(&Vec3::new(0., 0., 1.)) - n.unit_vector()).near_zero());
+    }
+}
@@ -0,0 +1,325 @@
+use std::rc::Rc;
+
+use crate::{
+    hittable::{HitRecord, Hittable, HittableList, ObjectFlags},
+    interval::Interval,
+    ray::Ray,
+    vec3::{Color3, LIGHT_BLUE, WHITE},
+};
+
+/// Light arriving along rays that leave the scene.
+#[derive(Clone, Copy, Debug, Default, PartialEq)]
+pub enum Background {
+    /// Gradient from white at the horizon to light blue overhead
+    #[default]
+    Sky,
+    Color(Color3),
+}
+
+/// The objects to render, and which of them to sample as lights.
+#[derive(Default)]
+pub struct Scene<'a> {
+    pub background: Background,
+    /// Emissive objects, also contained in `world`
+    pub lights: HittableList<'a>,
+    pub world: HittableList<'a>,
+}
+
+impl<'a> Scene<'a> {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn with_background(mut self, background: Background) -> Self {
+        self.background = background;
+        self
+    }
+
+    /// Add an object, returning its ID, e.g. for light linking.
+    pub fn add<T>(&mut self, object: T) -> u32
+    where
+        T: Hittable + 'a,
+    {
+        self.world.add(object)
+    }
+
+    pub fn add_with_flags<T>(&mut self, object: T, flags: ObjectFlags) -> u32
+    where
+        T: Hittable + 'a,
+    {
+        self.world.add_with_flags(object, flags)
+    }
+
+    /// Add an emissive object, which integrators may sample directly.
+    pub fn add_light<T>(&mut self, light: T) -> u32
+    where
+        T: Hittable + 'a,
+    {
+        self.add_light_with_flags(light, ObjectFlags::default())
+    }
+
+    pub fn add_light_with_flags<T>(&mut self, light: T, flags: ObjectFlags) -> u32
+    where
+        T: Hittable + 'a,
+    {
+        let light = Rc::new(light);
+        let id = self.world.add_with_flags(Rc::clone(&light), flags.clone());
+        self.lights.add_entry(id, light, flags);
+        id
+    }
+
+    /// Returns the light emitted at `rec` onto the object `receiver`, or towards the camera
+    /// if `None`, following the light linking of the emitter.
+    pub fn emitted(&self, rec: &HitRecord, receiver: Option<u32>) -> Color3 {
+        let black = Color3::new(0., 0., 0.);
+        let emitted = rec.material.emitted(rec);
+        match emitted == black || receiver.is_none_or(|id| self.is_linked(rec.object_id, id)) {
+            true => emitted,
+            false => black,
+        }
+    }
+
+    /// Returns the light found along `r` past the object hit at `rec`, onto the object
+    /// `receiver`, if the object doesn't cast shadows. Rays leaving a surface then see
+    /// through it, as rays towards sampled lights do; the camera still sees the object.
+    pub fn emitted_behind(&self, r: &Ray, rec: &HitRecord, receiver: Option<u32>) -> Color3 {
+        let casts_shadow = self
+            .world
+            .flags(rec.object_id)
+            .is_none_or(|flags| flags.shadow);
+        if receiver.is_none() || casts_shadow {
+            return Color3::new(0., 0., 0.);
+        }
+        match self
+            .world
+            .hit_shadowing(r, &Interval::new(rec.t, f64::INFINITY))
+        {
+            Some(behind) => self.emitted(&behind, receiver),
+            None => self.background(r),
+        }
+    }
+
+    /// Whether the light with ID `light` illuminates the object with ID `object`.
+    pub fn is_linked(&self, light: u32, object: u32) -> bool {
+        self.world
+            .flags(light)
+            .is_none_or(|flags| flags.light_link.lights(object))
+    }
+
+    /// Whether the camera sees a hole instead of the object hit at `rec`.
+    pub fn is_holdout(&self, rec: &HitRecord) -> bool {
+        self.world
+            .flags(rec.object_id)
+            .is_some_and(|flags| flags.holdout)
+    }
+
+    pub fn background(&self, r: &Ray) -> Color3 {
+        match self.background {
+            Background::Sky => {
+                let unit_direction = r.direction.unit_vector();
+                let a = 0.5 * (unit_direction.y + 1.);
+                (1. - a) * WHITE + a * LIGHT_BLUE
+            }
+            Background::Color(color) => color,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{
+        camera::Camera,
+        hittable::{LightLink, Sphere},
+        interval::Interval,
+        material::{DiffuseLight, Lambertian, Material},
+        ray::RayKind,
+        vec3::{Point3, Vec3},
+    };
+
+    #[test]
+    fn object_flags() {
+        // Arrange
+        let mut scene = Scene::new();
+        let material = Rc::new(Lambertian::new(Color3::new(0.5, 0.5, 0.5)));
+        let hidden = ObjectFlags {
+            camera: false,
+            ..Default::default()
+        };
+        let ball = scene.add_with_flags(Sphere::new(Point3::new(0., 0., 0.), 1., material), hidden);
+        let emit = Color3::new(4., 4., 4.);
+        let lamp = scene.add_light_with_flags(
+            Sphere::new(
+                Point3::new(0., 0., -5.),
+                1.,
+                Rc::new(DiffuseLight::new(emit)),
+            ),
+            ObjectFlags {
+                light_link: LightLink::Exclude(vec![ball]),
+                ..Default::default()
+            },
+        );
+        let r = Ray::new(Point3::new(0., 0., 5.), Vec3::new(0., 0., -1.));
+        let ray_t = Interval::new(0.001, f64::INFINITY);
+
+        // Act
+        let seen = scene
+            .world
+            .hit(&r.clone().with_kind(RayKind::Camera), &ray_t)
+            .unwrap();
+        let diffuse = scene.world.hit(&r, &ray_t).unwrap();
+
+        // Assert
+        assert_eq!(seen.object_id, lamp);
+        assert_eq!(diffuse.object_id, ball);
+        assert_eq!(scene.emitted(&seen, None), emit);
+        assert_eq!(scene.emitted(&seen, Some(ball)), Color3::new(0., 0., 0.));
+        assert!(scene.world.occluded(&r, &ray_t));
+    }
+
+    #[test]
+    fn holdout() {
+        // Arrange
+        let material: Rc<dyn Material> = Rc::new(Lambertian::new(Color3::new(0.5, 0.5, 0.5)));
+        let scene = |holdout| {
+            let mut scene = Scene::new().with_background(Background::Color(WHITE));
+            let flags = ObjectFlags {
+                holdout,
+                ..Default::default()
+            };
+            scene.add_with_flags(
+                Sphere::new(Point3::new(0., 0., -3.), 2., Rc::clone(&material)),
+                flags,
+            );
+            scene
+        };
+        let camera = Camera::builder()
+            .resolution(4, 4)
+            .samples_per_pixel(4)
+            .build()
+            .unwrap();
+
+        // Act
+        let held_out = camera.render_image(&scene(true)).unwrap();
+        let lit = camera.render_image(&scene(false)).unwrap();
+
+        // Assert
+        assert_eq!(held_out.get(1, 1), Color3::new(0., 0., 0.));
+        assert!(lit.get(1, 1).x > 0.);
+    }
+
+    #[test]
+    fn reflection_and_refraction_flags() {
+        // Arrange
+        let mut scene = Scene::new();
+        let material: Rc<dyn Material> = Rc::new(Lambertian::new(Color3::new(0.5, 0.5, 0.5)));
+        let unreflected = scene.add_with_flags(
+            Sphere::new(Point3::new(0., 0., -5.), 1., Rc::clone(&material)),
+            ObjectFlags {
+                reflection: false,
+                ..Default::default()
+            },
+        );
+        let unrefracted = scene.add_with_flags(
+            Sphere::new(Point3::new(0., 0., 5.), 1., material),
+            ObjectFlags {
+                refraction: false,
+                ..Default::default()
+            },
+        );
+        let ray_t = Interval::new(0.001, f64::INFINITY);
+        let forward = Ray::new(Point3::new(0., 0., 0.), Vec3::new(0., 0., -1.));
+        let backward = Ray::new(Point3::new(0., 0., 0.), Vec3::new(0., 0., 1.));
+
+        // Act
+        let hit = |r: &Ray, kind| scene.world.hit(&r.clone().with_kind(kind), &ray_t);
+
+        // Assert
+        assert!(hit(&forward, RayKind::Reflection).is_none());
+        assert_eq!(
+            hit(&forward, RayKind::Refraction).unwrap().object_id,
+            unreflected
+        );
+        assert!(hit(&backward, RayKind::Refraction).is_none());
+        assert_eq!(
+            hit(&backward, RayKind::Reflection).unwrap().object_id,
+            unrefracted
+        );
+    }
+
+    #[test]
+    fn shadow_flag() {
+        // Arrange
+        let mut scene = Scene::new();
+        let material: Rc<dyn Material> = Rc::new(Lambertian::new(Color3::new(0.5, 0.5, 0.5)));
+        let occluder = scene.add_with_flags(
+            Sphere::new(Point3::new(0., 0., -2.), 1., material),
+            ObjectFlags {
+                shadow: false,
+                ..Default::default()
+            },
+        );
+        let emit = Color3::new(4., 4., 4.);
+        scene.add_light(Sphere::new(
+            Point3::new(0., 0., -5.),
+            1.,
+            Rc::new(DiffuseLight::new(emit)),
+        ));
+        let r = Ray::new(Point3::new(0., 0., 0.), Vec3::new(0., 0., -1.));
+        let ray_t = Interval::new(0.001, f64::INFINITY);
+
+        // Act
+        let occluded = scene.world.occluded(&r, &Interval::new(0.001, 3.5));
+        let rec = scene.world.hit(&r, &ray_t).unwrap();
+        let behind = scene.emitted_behind(&r, &rec, Some(0));
+        let seen = scene.emitted_behind(&r, &rec, None);
+
+        // Assert
+        assert!(!occluded);
+        // Rays leaving a surface still hit it, and also find the light behind it.
+        assert_eq!(rec.object_id, occluder);
+        assert_eq!(behind, emit);
+        assert_eq!(seen, Color3::new(0., 0., 0.));
+    }
+
+    #[test]
+    fn light_link_include() {
+        // Arrange
+        let mut scene = Scene::new();
+        let material: Rc<dyn Material> = Rc::new(Lambertian::new(Color3::new(0.5, 0.5, 0.5)));
+        let lit = scene.add(Sphere::new(
+            Point3::new(2., 0., 0.),
+            1.,
+            Rc::clone(&material),
+        ));
+        let unlit = scene.add(Sphere::new(Point3::new(-2., 0., 0.), 1., material));
+        let emit = Color3::new(4., 4., 4.);
+        let lamp = scene.add_light_with_flags(
+            Sphere::new(
+                Point3::new(0., 0., -5.),
+                1.,
+                Rc::new(DiffuseLight::new(emit)),
+            ),
+            ObjectFlags {
+                light_link: LightLink::Include(vec![lit]),
+                ..Default::default()
+            },
+        );
+        let r = Ray::new(Point3::new(0., 0., 0.), Vec3::new(0., 0., -1.));
+        let rec = scene
+            .world
+            .hit(&r, &Interval::new(0.001, f64::INFINITY))
+            .unwrap();
+
+        // Act
+        let onto_lit = scene.emitted(&rec, Some(lit));
+        let onto_unlit = scene.emitted(&rec, Some(unlit));
+
+        // Assert
+        assert_eq!(rec.object_id, lamp);
+        assert_eq!(onto_lit, emit);
+        assert_eq!(onto_unlit, Color3::new(0., 0., 0.));
+        assert!(scene.is_linked(lamp, lit));
+        assert!(!scene.is_linked(lamp, unlit));
+    }
+}
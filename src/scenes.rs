@@ -0,0 +1,434 @@
+//! Built-in reference scenes, for benchmarks and regression tests.
+
+use std::rc::Rc;
+
+use crate::{
+    camera::{Camera, CameraBuilder},
+    hittable::{HittableList, Sphere, Triangle},
+    integrator::{NeePathIntegrator, PhotonMapIntegrator},
+    material::{Checker, Dielectric, DiffuseLight, Lambertian, Material, Metal},
+    scene::{Background, Scene},
+    util::{f64, random_double, random_double_minmax},
+    vec3::{Color3, Point3, Vec3},
+};
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum ReferenceScene {
+    /// Two large spheres with a checkerboard material
+    CheckeredSpheres,
+    /// The Cornell box, lit by an area light in the ceiling
+    CornellBox,
+    /// The cover of "Ray Tracing in One Weekend": a 22×22 grid of small random spheres
+    /// around three large ones
+    FinalScene,
+    /// Glass spheres focusing a small light onto a diffuse floor, rendered with a photon map
+    GlassCaustics,
+    /// Diffuse, hollow glass and fuzzy metal spheres side by side
+    MaterialTest,
+    /// Triangle meshes: an icosphere, a pyramid and a box
+    MeshTest,
+}
+
+impl ReferenceScene {
+    pub const ALL: [ReferenceScene; 6] = [
+        ReferenceScene::CheckeredSpheres,
+        ReferenceScene::CornellBox,
+        ReferenceScene::FinalScene,
+        ReferenceScene::GlassCaustics,
+        ReferenceScene::MaterialTest,
+        ReferenceScene::MeshTest,
+    ];
+
+    pub fn name(self) -> &'static str {
+        match self {
+            ReferenceScene::CheckeredSpheres => "checkered_spheres",
+            ReferenceScene::CornellBox => "cornell_box",
+            ReferenceScene::FinalScene => "final_scene",
+            ReferenceScene::GlassCaustics => "glass_caustics",
+            ReferenceScene::MaterialTest => "material_test",
+            ReferenceScene::MeshTest => "mesh_test",
+        }
+    }
+
+    pub fn from_name(name: &str) -> Option<Self> {
+        Self::ALL.into_iter().find(|scene| scene.name() == name)
+    }
+
+    /// Returns the scene and the settings of a camera framing it, which callers may change
+    /// before building it.
+    pub fn build(self) -> (Scene<'static>, CameraBuilder) {
+        match self {
+            ReferenceScene::CheckeredSpheres => checkered_spheres(),
+            ReferenceScene::CornellBox => cornell_box(),
+            ReferenceScene::FinalScene => final_scene(),
+            ReferenceScene::GlassCaustics => glass_caustics(),
+            ReferenceScene::MaterialTest => material_test(),
+            ReferenceScene::MeshTest => mesh_test(),
+        }
+    }
+}
+
+fn checkered_spheres() -> (Scene<'static>, CameraBuilder) {
+    let mut scene = Scene::new();
+    let checker: Rc<dyn Material> = Rc::new(Checker::new(
+        0.32,
+        Color3::new(0.2, 0.3, 0.1),
+        Color3::new(0.9, 0.9, 0.9),
+    ));
+    scene.add(Sphere::new(
+        Point3::new(0., -10., 0.),
+        10.,
+        Rc::clone(&checker),
+    ));
+    scene.add(Sphere::new(Point3::new(0., 10., 0.), 10., checker));
+
+    let camera = Camera::builder()
+        .lookfrom(Point3::new(13., 2., 3.))
+        .lookat(Point3::new(0., 0., 0.))
+        .resolution(400, 225)
+        .samples_per_pixel(100)
+        .vfov(20.);
+    (scene, camera)
+}
+
+fn cornell_box() -> (Scene<'static>, CameraBuilder) {
+    let mut scene = Scene::new().with_background(Background::Color(Color3::new(0., 0., 0.)));
+    let red: Rc<dyn Material> = Rc::new(Lambertian::new(Color3::new(0.65, 0.05, 0.05)));
+    let white: Rc<dyn Material> = Rc::new(Lambertian::new(Color3::new(0.73, 0.73, 0.73)));
+    let green: Rc<dyn Material> = Rc::new(Lambertian::new(Color3::new(0.12, 0.45, 0.15)));
+    let light: Rc<dyn Material> = Rc::new(DiffuseLight::new(Color3::new(15., 15., 15.)));
+
+    let size = 555.;
+    let (x, y, z) = (
+        Vec3::new(size, 0., 0.),
+        Vec3::new(0., size, 0.),
+        Vec3::new(0., 0., size),
+    );
+    let origin = Point3::new(0., 0., 0.);
+    scene.add(quad(origin + x, y, z, green));
+    scene.add(quad(origin, y, z, red));
+    scene.add(quad(origin, x, z, Rc::clone(&white)));
+    scene.add(quad(origin + y, x, z, Rc::clone(&white)));
+    scene.add(quad(origin + z, x, y, Rc::clone(&white)));
+    // Counterclockwise seen from below, so the light faces down.
+    scene.add_light(quad(
+        Point3::new(343., 554., 332.),
+        Vec3::new(-130., 0., 0.),
+        Vec3::new(0., 0., -105.),
+        light,
+    ));
+
+    scene.add(cuboid(
+        Point3::new(265., 0., 295.),
+        Point3::new(430., 330., 460.),
+        white,
+    ));
+    scene.add(Sphere::new(
+        Point3::new(190., 90., 190.),
+        90.,
+        Rc::new(Dielectric::new(1.5)),
+    ));
+
+    let camera = Camera::builder()
+        .integrator(NeePathIntegrator::new(50))
+        .lookfrom(Point3::new(278., 278., -800.))
+        .lookat(Point3::new(278., 278., 0.))
+        .resolution(400, 400)
+        .samples_per_pixel(200)
+        .vfov(40.);
+    (scene, camera)
+}
+
+fn final_scene() -> (Scene<'static>, CameraBuilder) {
+    let mut scene = Scene::new();
+    let ground = Rc::new(Lambertian::new(Color3::new(0.5, 0.5, 0.5)));
+    scene.add(Sphere::new(Point3::new(0., -1000., 0.), 1000., ground));
+
+    for a in -11..11 {
+        for b in -11..11 {
+            let choose_mat = random_double();
+            let center = Point3::new(
+                f64(a) + 0.9 * random_double(),
+                0.2,
+                f64(b) + 0.9 * random_double(),
+            );
+            if (center - Point3::new(4., 0.2, 0.)).length() <= 0.9 {
+                continue;
+            }
+
+            let material: Rc<dyn Material> = if choose_mat < 0.8 {
+                Rc::new(Lambertian::new(Color3::random() * Color3::random()))
+            } else if choose_mat < 0.95 {
+                let albedo = Color3::random_minmax(0.5, 1.);
+                Rc::new(Metal::new(albedo, random_double_minmax(0., 0.5)))
+            } else {
+                Rc::new(Dielectric::new(1.5))
+            };
+            scene.add(Sphere::new(center, 0.2, material));
+        }
+    }
+
+    scene.add(Sphere::new(
+        Point3::new(0., 1., 0.),
+        1.,
+        Rc::new(Dielectric::new(1.5)),
+    ));
+    scene.add(Sphere::new(
+        Point3::new(-4., 1., 0.),
+        1.,
+        Rc::new(Lambertian::new(Color3::new(0.4, 0.2, 0.1))),
+    ));
+    scene.add(Sphere::new(
+        Point3::new(4., 1., 0.),
+        1.,
+        Rc::new(Metal::new(Color3::new(0.7, 0.6, 0.5), 0.)),
+    ));
+
+    let camera = Camera::builder()
+        .defocus_angle(0.6)
+        .focus_dist(10.)
+        .lookfrom(Point3::new(13., 2., 3.))
+        .lookat(Point3::new(0., 0., 0.))
+        .resolution(400, 225)
+        .samples_per_pixel(100)
+        .vfov(20.);
+    (scene, camera)
+}
+
+fn glass_caustics() -> (Scene<'static>, CameraBuilder) {
+    let mut scene = Scene::new().with_background(Background::Color(Color3::new(0., 0., 0.)));
+    let floor = Rc::new(Lambertian::new(Color3::new(0.6, 0.6, 0.6)));
+    let glass: Rc<dyn Material> = Rc::new(Dielectric::new(1.5));
+    scene.add(Sphere::new(Point3::new(0., -1000., 0.), 1000., floor));
+    scene.add(Sphere::new(
+        Point3::new(-1.6, 0.8, 0.),
+        0.8,
+        Rc::clone(&glass),
+    ));
+    scene.add(Sphere::new(
+        Point3::new(0.6, 1., -0.5),
+        1.,
+        Rc::clone(&glass),
+    ));
+    scene.add(Sphere::new(Point3::new(1.8, 0.5, 1.2), 0.5, glass));
+    scene.add_light(Sphere::new(
+        Point3::new(-1., 5., -4.),
+        0.5,
+        Rc::new(DiffuseLight::new(Color3::new(100., 100., 100.))),
+    ));
+
+    let camera = Camera::builder()
+        .integrator(PhotonMapIntegrator::new(20, 500_000, 0.1))
+        .lookfrom(Point3::new(0., 4., 8.))
+        .lookat(Point3::new(0., 0.6, 0.))
+        .resolution(400, 225)
+        .samples_per_pixel(64)
+        .vfov(35.);
+    (scene, camera)
+}
+
+fn material_test() -> (Scene<'static>, CameraBuilder) {
+    let mut scene = Scene::new();
+    let ground = Rc::new(Lambertian::new(Color3::new(0.8, 0.8, 0.)));
+    scene.add(Sphere::new(Point3::new(0., -100.5, -1.), 100., ground));
+    scene.add(Sphere::new(
+        Point3::new(0., 0., -1.2),
+        0.5,
+        Rc::new(Lambertian::new(Color3::new(0.1, 0.2, 0.5))),
+    ));
+    // A bubble of air inside the glass makes it a hollow sphere.
+    scene.add(Sphere::new(
+        Point3::new(-1., 0., -1.),
+        0.5,
+        Rc::new(Dielectric::new(1.5)),
+    ));
+    scene.add(Sphere::new(
+        Point3::new(-1., 0., -1.),
+        0.4,
+        Rc::new(Dielectric::new(1. / 1.5)),
+    ));
+    scene.add(Sphere::new(
+        Point3::new(1., 0., -1.),
+        0.5,
+        Rc::new(Metal::new(Color3::new(0.8, 0.6, 0.2), 0.3)),
+    ));
+
+    let camera = Camera::builder()
+        .lookfrom(Point3::new(0., 0., 0.))
+        .lookat(Point3::new(0., 0., -1.))
+        .resolution(400, 225)
+        .samples_per_pixel(100)
+        .vfov(90.);
+    (scene, camera)
+}
+
+fn mesh_test() -> (Scene<'static>, CameraBuilder) {
+    let mut scene = Scene::new();
+    let ground = Rc::new(Checker::new(
+        0.5,
+        Color3::new(0.2, 0.2, 0.2),
+        Color3::new(0.8, 0.8, 0.8),
+    ));
+    scene.add(Sphere::new(Point3::new(0., -1000., 0.), 1000., ground));
+    scene.add(icosphere(
+        Point3::new(0., 1., 0.),
+        1.,
+        2,
+        Rc::new(Metal::new(Color3::new(0.8, 0.8, 0.9), 0.1)),
+    ));
+    scene.add(pyramid(
+        Point3::new(-2.5, 0., 0.),
+        1.6,
+        1.5,
+        Rc::new(Lambertian::new(Color3::new(0.7, 0.3, 0.1))),
+    ));
+    scene.add(cuboid(
+        Point3::new(1.8, 0., -0.6),
+        Point3::new(3., 1.2, 0.6),
+        Rc::new(Dielectric::new(1.5)),
+    ));
+
+    let camera = Camera::builder()
+        .lookfrom(Point3::new(0., 3., 8.))
+        .lookat(Point3::new(0., 0.8, 0.))
+        .resolution(400, 225)
+        .samples_per_pixel(100)
+        .vfov(40.);
+    (scene, camera)
+}
+
+/// Returns the parallelogram with corner `q` and edges `u` and `v`, as two triangles facing
+/// along `u × v`.
+fn quad(q: Point3, u: Vec3, v: Vec3, material: Rc<dyn Material>) -> HittableList<'static> {
+    let mut sides = HittableList::new();
+    sides.add(Triangle::new(q, q + u, q + u + v, Rc::clone(&material)));
+    sides.add(Triangle::new(q, q + u + v, q + v, material));
+    sides
+}
+
+/// Returns the axis-aligned box between opposite corners `a` and `b`.
+fn cuboid(a: Point3, b: Point3, material: Rc<dyn Material>) -> HittableList<'static> {
+    let min = Point3::new(a.x.min(b.x), a.y.min(b.y), a.z.min(b.z));
+    let max = Point3::new(a.x.max(b.x), a.y.max(b.y), a.z.max(b.z));
+    let dx = Vec3::new(max.x - min.x, 0., 0.);
+    let dy = Vec3::new(0., max.y - min.y, 0.);
+    let dz = Vec3::new(0., 0., max.z - min.z);
+
+    let mut sides = HittableList::new();
+    for (q, u, v) in [
+        (min, dx, dy),
+        (min + dz, dx, dy),
+        (min, dx, dz),
+        (min + dy, dx, dz),
+        (min, dy, dz),
+        (min + dx, dy, dz),
+    ] {
+        sides.add(quad(q, u, v, Rc::clone(&material)));
+    }
+    sides
+}
+
+/// Returns a square pyramid standing on its base, centered at `base`.
+fn pyramid(
+    base: Point3,
+    width: f64,
+    height: f64,
+    material: Rc<dyn Material>,
+) -> HittableList<'static> {
+    let h = width / 2.;
+    let corners = [
+        base + Vec3::new(-h, 0., -h),
+        base + Vec3::new(h, 0., -h),
+        base + Vec3::new(h, 0., h),
+        base + Vec3::new(-h, 0., h),
+    ];
+    let apex = base + Vec3::new(0., height, 0.);
+
+    let mut faces = HittableList::new();
+    for i in 0..4 {
+        let (a, b) = (corners[i], corners[(i + 1) % 4]);
+        faces.add(Triangle::new(a, b, apex, Rc::clone(&material)));
+    }
+    faces.add(quad(
+        corners[0],
+        corners[1] - corners[0],
+        corners[3] - corners[0],
+        material,
+    ));
+    faces
+}
+
+/// Returns a sphere approximated by an icosahedron whose faces are split in four
+/// `subdivisions` times.
+fn icosphere(
+    center: Point3,
+    radius: f64,
+    subdivisions: u32,
+    material: Rc<dyn Material>,
+) -> HittableList<'static> {
+    let phi = (1. + 5_f64.sqrt()) / 2.;
+    let vertices = [
+        (-1., phi, 0.),
+        (1., phi, 0.),
+        (-1., -phi, 0.),
+        (1., -phi, 0.),
+        (0., -1., phi),
+        (0., 1., phi),
+        (0., -1., -phi),
+        (0., 1., -phi),
+        (phi, 0., -1.),
+        (phi, 0., 1.),
+        (-phi, 0., -1.),
+        (-phi, 0., 1.),
+    ]
+    .map(|(x, y, z)| Vec3::new(x, y, z).unit_vector());
+    let mut faces = [
+        [0, 11, 5],
+        [0, 5, 1],
+        [0, 1, 7],
+        [0, 7, 10],
+        [0, 10, 11],
+        [1, 5, 9],
+        [5, 11, 4],
+        [11, 10, 2],
+        [10, 7, 6],
+        [7, 1, 8],
+        [3, 9, 4],
+        [3, 4, 2],
+        [3, 2, 6],
+        [3, 6, 8],
+        [3, 8, 9],
+        [4, 9, 5],
+        [2, 4, 11],
+        [6, 2, 10],
+        [8, 6, 7],
+        [9, 8, 1],
+    ]
+    .map(|face| face.map(|i| vertices[i]))
+    .to_vec();
+
+    for _ in 0..subdivisions {
+        faces = faces
+            .into_iter()
+            .flat_map(|[a, b, c]| {
+                let (ab, bc, ca) = (
+                    (a + b).unit_vector(),
+                    (b + c).unit_vector(),
+                    (c + a).unit_vector(),
+                );
+                [[a, ab, ca], [b, bc, ab], [c, ca, bc], [ab, bc, ca]]
+            })
+            .collect();
+    }
+
+    let mut mesh = HittableList::new();
+    for [a, b, c] in faces {
+        mesh.add(Triangle::new(
+            center + radius * a,
+            center + radius * b,
+            center + radius * c,
+            Rc::clone(&material),
+        ));
+    }
+    mesh
+}
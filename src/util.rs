@@ -1,14 +1,24 @@
+use std::cell::RefCell;
+
+use rand::{rngs::StdRng, Rng, SeedableRng};
+
 pub fn f64(a: i32) -> f64 {
     a.into()
 }
 
-pub fn linear_to_gamma(linear_component: f64) -> f64 {
-    linear_component.sqrt()
+thread_local! {
+    static RNG: RefCell<StdRng> = RefCell::new(StdRng::from_entropy());
+}
+
+/// Reseed the random number generator of the current thread, so what it renders next
+/// repeats exactly.
+pub fn seed_random(seed: u64) {
+    RNG.with(|rng| *rng.borrow_mut() = StdRng::seed_from_u64(seed));
 }
 
 /// Returns a random real in [0,1).
 pub fn random_double() -> f64 {
-    rand::random::<f64>()
+    RNG.with(|rng| rng.borrow_mut().gen())
 }
 
 /// Returns a random real in [min,max).
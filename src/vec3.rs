@@ -1,8 +1,13 @@
-use std::ops::{Add, AddAssign, Div, DivAssign, Mul, MulAssign, Neg, Sub, SubAssign};
+use std::{
+    io::{self, Write},
+    ops::{Add, AddAssign, Div, DivAssign, Mul, MulAssign, Neg, Sub, SubAssign},
+};
 
 use crate::{
+    color::srgb_oetf,
     interval::Interval,
-    util::{linear_to_gamma, random_double, random_double_minmax},
+    sampling::{concentric_disk, random_2d, uniform_sphere},
+    util::{random_double, random_double_minmax},
 };
 
 pub type Color3 = Vec3;
@@ -61,26 +66,9 @@ impl Vec3 {
         )
     }
 
+    /// Returns a random point in the unit disk in the xy-plane.
     pub fn random_in_unit_disk() -> Self {
-        loop {
-            let p = Self::new(
-                random_double_minmax(-1., 1.),
-                random_double_minmax(-1., 1.),
-                0.,
-            );
-            if p.length_squared() < 1. {
-                return p;
-            }
-        }
-    }
-
-    fn random_in_unit_sphere() -> Self {
-        loop {
-            let p = Self::random_minmax(-1., 1.);
-            if p.length_squared() < 1. {
-                return p;
-            }
-        }
+        concentric_disk(random_2d())
     }
 
     fn _random_on_hemisphere(normal: &Self) -> Self {
@@ -94,7 +82,7 @@ impl Vec3 {
     }
 
     pub fn random_unit_vector() -> Self {
-        Self::random_in_unit_sphere().unit_vector()
+        uniform_sphere(random_2d())
     }
 
     /// Reflect `self` on the surface normal `n`.
@@ -102,8 +90,16 @@ impl Vec3 {
         *self - 2. * self.dot(n) * *n
     }
 
+    /// Refract the unit vector `self` through the surface with normal `n`, by Snell's law.
+    ///
+    /// Past the critical angle there is no refracted ray, and all light is reflected, so
+    /// this returns the reflection instead.
     pub fn refract(&self, n: &Self, etai_over_etat: f64) -> Self {
         let cos_theta = (-*self).dot(n).min(1.);
+        let sin_theta = (1. - cos_theta * cos_theta).sqrt();
+        if etai_over_etat * sin_theta > 1. {
+            return self.reflect(n);
+        }
         let r_out_perp = etai_over_etat * (*self + cos_theta * *n);
         let r_out_parallel = -(1. - r_out_perp.length_squared()).abs().sqrt() * *n;
         r_out_perp + r_out_parallel
@@ -113,31 +109,26 @@ impl Vec3 {
         *self / self.length()
     }
 
-    pub fn write_color(&self, samples_per_pixel: u32) {
-        let mut r = self.x;
-        let mut g = self.y;
-        let mut b = self.z;
-
-        // Divide the color by the number of samples.
-        let samples_per_pixel = Into::<f64>::into(samples_per_pixel);
-        let scale = 1. / samples_per_pixel;
-        r *= scale;
-        g *= scale;
-        b *= scale;
-
-        // Apply the linear to gamma transform
-        r = linear_to_gamma(r);
-        g = linear_to_gamma(g);
-        b = linear_to_gamma(b);
+    /// Write the linear display color as the sRGB encoded [0,255] value of each component.
+    pub fn write_color(&self, out: &mut impl Write) -> io::Result<()> {
+        let r = srgb_oetf(self.x.max(0.));
+        let g = srgb_oetf(self.y.max(0.));
+        let b = srgb_oetf(self.z.max(0.));
 
         // Write the translated [0,255] value of each color component.
         const INTENSITY: Interval = Interval::new(0., 0.999);
-        println!(
+        writeln!(
+            out,
             "{} {} {}",
             (256. * INTENSITY.clamp(r)) as i64,
             (256. * INTENSITY.clamp(g)) as i64,
             (256. * INTENSITY.clamp(b)) as i64,
-        );
+        )
+    }
+
+    /// Returns the relative luminance of a linear Rec. 709 color.
+    pub fn luminance(&self) -> f64 {
+        0.2126 * self.x + 0.7152 * self.y + 0.0722 * self.z
     }
 }
 
@@ -324,6 +315,47 @@ mod tests {
         assert_eq!(b, result);
     }
 
+    #[test]
+    fn refract() {
+        // Arrange
+        let n = Vec3::new(0., 0., 1.);
+        let eta = 1. / 1.5;
+        let incident = (1..9).map(|i| {
+            let theta = f64::from(i) * 0.1;
+            Vec3::new(theta.sin(), 0., -theta.cos())
+        });
+
+        for a in incident {
+            // Act
+            let b = a.refract(&n, eta);
+
+            // Assert
+            // Snell's law: the sines of the angles to the normal are in the ratio of the
+            // indices, and the refracted ray stays in the plane of incidence.
+            assert!((b.length() - 1.).abs() < 1e-12);
+            assert!(b.z < 0.);
+            assert_eq!(b.y, 0.);
+            assert!((b.x - eta * a.x).abs() < 1e-12);
+        }
+        let straight = Vec3::new(0., 0., -1.).refract(&n, eta);
+        assert_eq!(straight, Vec3::new(0., 0., -1.));
+    }
+
+    #[test]
+    fn refract_total_internal_reflection() {
+        // Arrange
+        let n = Vec3::new(0., 0., 1.);
+        // Leaving glass at 60°, beyond the critical angle of about 41.8°
+        let a = Vec3::new(3f64.sqrt() / 2., 0., -0.5);
+
+        // Act
+        let b = a.refract(&n, 1.5);
+
+        // Assert
+        assert_eq!(b, a.reflect(&n));
+        assert!((b.length() - 1.).abs() < 1e-12);
+    }
+
     #[test]
     fn add() {
         // Arrange
@@ -0,0 +1,139 @@
+//! Regression tests that render each reference scene small, with a fixed seed, and compare
+//! it with its golden image in `tests/golden`.
+//!
+//! After an intended change to the output, rerun with `UPDATE_GOLDENS=1` to write new
+//! golden images. On a mismatch, the render and a difference image are written to
+//! `golden` in the target's temporary directory.
+
+use std::{env, fs, path::PathBuf};
+
+use hello_raytracing::{
+    image::Image, integrator::PhotonMapIntegrator, scenes::ReferenceScene, seed_random,
+    vec3::Color3,
+};
+
+const SEED: u64 = 0x5eed;
+const SAMPLES_PER_PIXEL: u32 = 8;
+/// Largest root mean square difference of the display values, in [0,1], that passes
+const TOLERANCE: f64 = 0.02;
+/// Scale of the differences in the difference image, so small ones are visible
+const DIFF_SCALE: f64 = 4.;
+
+#[test]
+fn checkered_spheres() {
+    check(ReferenceScene::CheckeredSpheres);
+}
+
+#[test]
+fn cornell_box() {
+    check(ReferenceScene::CornellBox);
+}
+
+#[test]
+fn final_scene() {
+    check(ReferenceScene::FinalScene);
+}
+
+#[test]
+fn glass_caustics() {
+    check(ReferenceScene::GlassCaustics);
+}
+
+#[test]
+fn material_test() {
+    check(ReferenceScene::MaterialTest);
+}
+
+#[test]
+fn mesh_test() {
+    check(ReferenceScene::MeshTest);
+}
+
+fn check(reference: ReferenceScene) {
+    let name = reference.name();
+    let golden_path = PathBuf::from(env!("CARGO_MANIFEST_DIR"))
+        .join("tests/golden")
+        .join(format!("{name}.ppm"));
+    let image = render(reference);
+
+    if env::var_os("UPDATE_GOLDENS").is_some() {
+        image.save_ppm(&golden_path).unwrap();
+        return;
+    }
+    let golden = Image::load_pnm(&golden_path).unwrap_or_else(|e| {
+        panic!("no golden image for {name} ({e}), rerun with UPDATE_GOLDENS=1 to write it")
+    });
+    let actual = display_values(&image);
+    assert_eq!(
+        (actual.width(), actual.height()),
+        (golden.width(), golden.height()),
+        "{name} differs in size from its golden image"
+    );
+
+    let error = rmse(&actual, &golden);
+    if error > TOLERANCE {
+        let out = PathBuf::from(env!("CARGO_TARGET_TMPDIR")).join("golden");
+        fs::create_dir_all(&out).unwrap();
+        let actual_path = out.join(format!("{name}.ppm"));
+        let diff_path = out.join(format!("{name}.diff.ppm"));
+        image.save_ppm(&actual_path).unwrap();
+        diff(&actual, &golden).save_ppm(&diff_path).unwrap();
+        panic!(
+            "{name} differs from its golden image by RMSE {error:.4} > {TOLERANCE}, see {} and {}",
+            actual_path.display(),
+            diff_path.display()
+        );
+    }
+}
+
+/// Render `reference` at a fraction of its resolution and sample count.
+fn render(reference: ReferenceScene) -> Image {
+    seed_random(SEED);
+    let (scene, builder) = reference.build();
+    let (width, height) = match reference {
+        ReferenceScene::CornellBox => (40, 40),
+        _ => (64, 36),
+    };
+    let builder = builder
+        .resolution(width, height)
+        .samples_per_pixel(SAMPLES_PER_PIXEL);
+    // The full photon count takes too long for a test.
+    let builder = match reference {
+        ReferenceScene::GlassCaustics => {
+            builder.integrator(PhotonMapIntegrator::new(20, 20_000, 0.2))
+        }
+        _ => builder,
+    };
+    builder.build().unwrap().render_image(&scene).unwrap()
+}
+
+/// Returns the values of `image` as written to a PPM file, scaled to [0,1], which is how
+/// golden images load.
+fn display_values(image: &Image) -> Image {
+    let mut ppm = Vec::new();
+    image.write_ppm(&mut ppm).unwrap();
+    Image::read_pnm(&ppm).unwrap()
+}
+
+fn rmse(a: &Image, b: &Image) -> f64 {
+    let sum = a
+        .pixels()
+        .iter()
+        .zip(b.pixels())
+        .map(|(a, b)| (*a - *b).length_squared())
+        .sum::<f64>();
+    (sum / (3 * a.pixels().len()) as f64).sqrt()
+}
+
+fn diff(a: &Image, b: &Image) -> Image {
+    let mut image = Image::new(a.width(), a.height());
+    for (out, (a, b)) in image
+        .pixels_mut()
+        .iter_mut()
+        .zip(a.pixels().iter().zip(b.pixels()))
+    {
+        let d = *a - *b;
+        *out = DIFF_SCALE * Color3::new(d.x.abs(), d.y.abs(), d.z.abs());
+    }
+    image
+}